@@ -0,0 +1,387 @@
+use crate::charset::Charset;
+use crate::display::DisplayOptions;
+use crate::scan::{DirectoryObject, FileObject};
+use crate::styles::{Color, TextStyle};
+use crate::system::terminal::{enable_raw_mode, read_key, terminal_size, Key};
+use std::collections::HashSet;
+use std::io::{self, Write};
+
+enum Entry<'a> {
+    Directory(&'a DirectoryObject),
+    File(&'a FileObject),
+}
+
+impl Entry<'_> {
+    fn get_path(&self) -> &str {
+        match self {
+            Entry::Directory(directory) => &directory.path,
+            Entry::File(file) => &file.path,
+        }
+    }
+}
+
+struct Row<'a> {
+    prefix: String,
+    entry: Entry<'a>,
+}
+
+struct Browser<'a> {
+    tree: &'a DirectoryObject,
+    options: DisplayOptions,
+    expanded: HashSet<String>,
+    cursor: usize,
+    scroll: usize,
+    filter: String,
+    is_editing_filter: bool,
+}
+
+impl<'a> Browser<'a> {
+    fn new(tree: &'a DirectoryObject, options: DisplayOptions) -> Browser<'a> {
+        let mut expanded = HashSet::new();
+        expanded.insert(tree.path.clone());
+
+        Browser {
+            tree,
+            options,
+            expanded,
+            cursor: 0,
+            scroll: 0,
+            filter: String::new(),
+            is_editing_filter: false,
+        }
+    }
+
+    fn rows(&self) -> Vec<Row<'a>> {
+        let mut rows = vec![Row {
+            prefix: String::new(),
+            entry: Entry::Directory(self.tree),
+        }];
+        self.add_rows(self.tree, "", &mut rows);
+        rows
+    }
+
+    fn add_rows(&self, directory: &'a DirectoryObject, indent: &str, rows: &mut Vec<Row<'a>>) {
+        if !self.filter.is_empty() && !self.directory_matches(directory) {
+            return;
+        }
+
+        let files: Vec<&FileObject> = directory
+            .files
+            .iter()
+            .filter(|file| self.filter.is_empty() || self.file_matches(file))
+            .collect();
+        let directories: Vec<&DirectoryObject> = directory
+            .directories
+            .iter()
+            .filter(|sub_directory| self.filter.is_empty() || self.directory_matches(sub_directory))
+            .collect();
+
+        for (i, file) in files.iter().enumerate() {
+            let is_last = i == files.len() - 1 && directories.is_empty();
+            rows.push(Row {
+                prefix: format!("{}{}", indent, Charset::current().branch(is_last)),
+                entry: Entry::File(file),
+            });
+        }
+
+        for (i, sub_directory) in directories.iter().enumerate() {
+            let is_last = i == directories.len() - 1;
+            rows.push(Row {
+                prefix: format!("{}{}", indent, Charset::current().branch(is_last)),
+                entry: Entry::Directory(sub_directory),
+            });
+
+            // Filtering expands every directory so all matches are visible
+            if !self.filter.is_empty() || self.expanded.contains(&sub_directory.path) {
+                let child_indent = format!("{}{}", indent, Charset::current().indent(is_last));
+                self.add_rows(sub_directory, &child_indent, rows);
+            }
+        }
+    }
+
+    fn file_matches(&self, file: &FileObject) -> bool {
+        file.full_name
+            .to_lowercase()
+            .contains(&self.filter.to_lowercase())
+    }
+
+    fn directory_matches(&self, directory: &DirectoryObject) -> bool {
+        directory
+            .base_name()
+            .to_lowercase()
+            .contains(&self.filter.to_lowercase())
+            || directory.files.iter().any(|file| self.file_matches(file))
+            || directory
+                .directories
+                .iter()
+                .any(|sub_directory| self.directory_matches(sub_directory))
+    }
+
+    fn format_row(&self, row: &Row) -> String {
+        match row.entry {
+            Entry::File(file) => format!(
+                "{}{}{}",
+                row.prefix,
+                if self.options.show_endings {
+                    &file.full_name
+                } else {
+                    &file.name
+                },
+                if self.options.show_file_sizes {
+                    format!(" ({})", file.size_string)
+                } else {
+                    "".to_string()
+                }
+            ),
+            Entry::Directory(directory) => format!(
+                "{}{} {}{}{}",
+                row.prefix,
+                if self.expanded.contains(&directory.path) || !self.filter.is_empty() {
+                    '▾'
+                } else {
+                    '▸'
+                },
+                directory.base_name(),
+                if self.options.show_file_counts_in_directories && directory.file_count > 0 {
+                    format!(" ({})", directory.file_count)
+                } else {
+                    "".to_string()
+                },
+                if self.options.show_directory_sizes {
+                    format!(" ({})", directory.size_string)
+                } else {
+                    "".to_string()
+                }
+            ),
+        }
+    }
+
+    fn render(
+        &mut self,
+        rows: &[Row],
+        (columns, lines): (usize, usize),
+        output: &mut impl Write,
+    ) -> io::Result<()> {
+        let visible_rows = lines.saturating_sub(2).max(1);
+
+        if self.cursor < self.scroll {
+            self.scroll = self.cursor;
+        } else if self.cursor >= self.scroll + visible_rows {
+            self.scroll = self.cursor + 1 - visible_rows;
+        }
+
+        let mut screen = String::from("\u{001b}[H");
+
+        let header = format!(
+            "{} | [x] extensions: {}  [f] file sizes: {}  [d] directory sizes: {}  [c] counts: {}",
+            self.tree.path,
+            on_off(self.options.show_endings),
+            on_off(self.options.show_file_sizes),
+            on_off(self.options.show_directory_sizes),
+            on_off(self.options.show_file_counts_in_directories)
+        );
+        screen.push_str(&format!(
+            "{}{}{}\u{001b}[K\r\n",
+            TextStyle::Bold.to_ansi_style_string(),
+            truncate(&header, columns),
+            TextStyle::Reset.to_ansi_style_string()
+        ));
+
+        for index in self.scroll..self.scroll + visible_rows {
+            if let Some(row) = rows.get(index) {
+                let line = truncate(&self.format_row(row), columns);
+                let style = match (index == self.cursor, &row.entry) {
+                    (true, _) => TextStyle::Invert.to_ansi_style_string(),
+                    (false, Entry::Directory(_)) => Color::Blue.to_ansi_color_string(),
+                    (false, Entry::File(_)) => String::new(),
+                };
+                screen.push_str(&format!(
+                    "{}{}{}",
+                    style,
+                    line,
+                    TextStyle::Reset.to_ansi_style_string()
+                ));
+            }
+            screen.push_str("\u{001b}[K\r\n");
+        }
+
+        let footer = if self.is_editing_filter {
+            format!("/{}", self.filter)
+        } else if !self.filter.is_empty() {
+            format!(
+                "filter: {} (esc to clear) | enter: open/select  s: select  q: quit",
+                self.filter
+            )
+        } else {
+            "arrows/hjkl: move  enter: open/select  s: select  /: filter  q: quit".to_string()
+        };
+        screen.push_str(&format!(
+            "{}{}{}\u{001b}[K",
+            TextStyle::Dim.to_ansi_style_string(),
+            truncate(&footer, columns),
+            TextStyle::Reset.to_ansi_style_string()
+        ));
+
+        output.write_all(screen.as_bytes())?;
+        output.flush()
+    }
+
+    /// Handle a key press, returns the selected path once the browser should exit
+    fn handle_key(&mut self, key: Key, rows: &[Row], lines: usize) -> Option<Option<String>> {
+        let page_size = lines.saturating_sub(2).max(1);
+        let last_row = rows.len().saturating_sub(1);
+
+        if let Key::Interrupt = key {
+            return Some(None);
+        }
+
+        if self.is_editing_filter {
+            match key {
+                Key::Enter => self.is_editing_filter = false,
+                Key::Escape => {
+                    self.is_editing_filter = false;
+                    self.filter.clear();
+                }
+                Key::Backspace => {
+                    self.filter.pop();
+                }
+                Key::Char(c) => self.filter.push(c),
+                _ => {}
+            }
+            self.cursor = 0;
+            return None;
+        }
+
+        match key {
+            Key::Up | Key::Char('k') => self.cursor = self.cursor.saturating_sub(1),
+            Key::Down | Key::Char('j') => self.cursor = (self.cursor + 1).min(last_row),
+            Key::PageUp => self.cursor = self.cursor.saturating_sub(page_size),
+            Key::PageDown => self.cursor = (self.cursor + page_size).min(last_row),
+            Key::Home | Key::Char('g') => self.cursor = 0,
+            Key::End | Key::Char('G') => self.cursor = last_row,
+            Key::Right | Key::Char('l') => {
+                if let Some(Entry::Directory(directory)) = rows.get(self.cursor).map(|r| &r.entry) {
+                    self.expanded.insert(directory.path.clone());
+                }
+            }
+            Key::Left | Key::Char('h') => self.collapse_or_go_to_parent(rows),
+            Key::Enter | Key::Char(' ') => match rows.get(self.cursor).map(|row| &row.entry) {
+                Some(Entry::Directory(directory)) => self.toggle_expanded(&directory.path),
+                Some(Entry::File(file)) => return Some(Some(file.path.clone())),
+                None => {}
+            },
+            Key::Char('s') => {
+                return Some(
+                    rows.get(self.cursor)
+                        .map(|row| row.entry.get_path().to_string()),
+                )
+            }
+            Key::Char('/') => self.is_editing_filter = true,
+            Key::Escape if !self.filter.is_empty() => {
+                self.filter.clear();
+                self.cursor = 0;
+            }
+            Key::Char('x') => self.options.show_endings = !self.options.show_endings,
+            Key::Char('f') => self.options.show_file_sizes = !self.options.show_file_sizes,
+            Key::Char('d') => {
+                self.options.show_directory_sizes = !self.options.show_directory_sizes
+            }
+            Key::Char('c') => {
+                self.options.show_file_counts_in_directories =
+                    !self.options.show_file_counts_in_directories
+            }
+            Key::Char('q') | Key::Escape => return Some(None),
+            _ => {}
+        }
+
+        None
+    }
+
+    fn toggle_expanded(&mut self, path: &str) {
+        if !self.expanded.remove(path) {
+            self.expanded.insert(path.to_string());
+        }
+    }
+
+    fn collapse_or_go_to_parent(&mut self, rows: &[Row]) {
+        let row = match rows.get(self.cursor) {
+            Some(row) => row,
+            None => return,
+        };
+
+        if let Entry::Directory(directory) = row.entry {
+            if self.filter.is_empty() && self.expanded.remove(&directory.path) && self.cursor != 0 {
+                return;
+            }
+        }
+
+        // Move to the closest row above with a shorter prefix, which is the parent directory
+        let depth = row.prefix.chars().count();
+        if let Some(parent) = (0..self.cursor)
+            .rev()
+            .find(|&index| rows[index].prefix.chars().count() < depth)
+        {
+            self.cursor = parent;
+        }
+    }
+}
+
+fn on_off(value: bool) -> &'static str {
+    if value {
+        "on"
+    } else {
+        "off"
+    }
+}
+
+fn truncate(line: &str, columns: usize) -> String {
+    line.chars().take(columns).collect()
+}
+
+/// Open an interactive browser for the tree, returns the path selected by the user
+pub fn browse(tree: &DirectoryObject, options: DisplayOptions) -> Result<Option<String>, String> {
+    let raw_mode = enable_raw_mode()?;
+
+    // The browser is drawn on stderr so the selected path can be piped from stdout
+    let mut output = io::stderr();
+    let mut input = io::stdin().lock();
+    let mut browser = Browser::new(tree, options);
+
+    // Switch to the alternate screen and hide the cursor
+    let _ = output.write_all(b"\x1b[?1049h\x1b[?25l\x1b[2J");
+
+    let mut rows = browser.rows();
+    let mut size = terminal_size().unwrap_or((80, 24));
+    let mut needs_redraw = true;
+
+    let result = loop {
+        if needs_redraw {
+            if let Err(e) = browser.render(&rows, size, &mut output) {
+                break Err(format!("failed to draw browser: {}", e));
+            }
+            needs_redraw = false;
+        }
+
+        let key = match read_key(&mut input) {
+            Ok(Some(key)) => key,
+            Ok(None) => continue,
+            Err(e) => break Err(format!("failed to read input: {}", e)),
+        };
+
+        // Asking for the size runs stty, so it is only done once per key press
+        size = terminal_size().unwrap_or((80, 24));
+        if let Some(selection) = browser.handle_key(key, &rows, size.1) {
+            break Ok(selection);
+        }
+
+        rows = browser.rows();
+        browser.cursor = browser.cursor.min(rows.len().saturating_sub(1));
+        needs_redraw = true;
+    };
+
+    let _ = output.write_all(b"\x1b[?25h\x1b[?1049l");
+    let _ = output.flush();
+    drop(raw_mode);
+
+    result
+}
@@ -0,0 +1,127 @@
+use crate::system::config::{get_optional_setting_from_config, ConfigOption};
+use crate::OS;
+use std::sync::OnceLock;
+
+pub const DEFAULT_CHARSET: &str = "unicode";
+
+/// Blocks filling one to seven eighths of a cell, for the end of a bar
+const PARTIAL_BLOCKS: [&str; 8] = ["", "▏", "▎", "▍", "▌", "▋", "▊", "▉"];
+
+/// Characters used to draw trees, decided once at startup by `set_charset`
+static CHARSET: OnceLock<Charset> = OnceLock::new();
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Charset {
+    Ascii,
+    Unicode,
+    Heavy,
+    Rounded,
+    Double,
+}
+
+impl Charset {
+    pub fn from_string(string: &str) -> Option<Charset> {
+        match string {
+            "ascii" => Some(Charset::Ascii),
+            "unicode" => Some(Charset::Unicode),
+            "heavy" => Some(Charset::Heavy),
+            "rounded" => Some(Charset::Rounded),
+            "double" => Some(Charset::Double),
+            _ => None,
+        }
+    }
+
+    /// The charset passed with --charset or set in the config
+    pub fn load(name: Option<String>, os: &OS) -> Result<Charset, String> {
+        let name = name
+            .or_else(|| {
+                get_optional_setting_from_config(ConfigOption::Charset, os)
+                    .map(|setting| setting.value)
+                    .filter(|value| !value.is_empty() && value != "None")
+            })
+            .unwrap_or(DEFAULT_CHARSET.to_string());
+
+        Charset::from_string(&name).ok_or(
+            "charset must be \"ascii\", \"unicode\", \"heavy\", \"rounded\" or \"double\""
+                .to_string(),
+        )
+    }
+
+    /// The charset set at startup, unicode if none was set
+    pub fn current() -> Charset {
+        CHARSET.get().copied().unwrap_or(Charset::Unicode)
+    }
+
+    /// Connector in front of an entry, like "├── " or "└── " for the last entry
+    pub fn branch(&self, is_last: bool) -> &'static str {
+        match (self, is_last) {
+            (Charset::Ascii, false) => "|-- ",
+            (Charset::Ascii, true) => "`-- ",
+            (Charset::Unicode, false) | (Charset::Rounded, false) => "├── ",
+            (Charset::Unicode, true) => "└── ",
+            (Charset::Rounded, true) => "╰── ",
+            (Charset::Heavy, false) => "┣━━ ",
+            (Charset::Heavy, true) => "┗━━ ",
+            (Charset::Double, false) => "╠══ ",
+            (Charset::Double, true) => "╚══ ",
+        }
+    }
+
+    /// Indentation below an entry, continuing the line unless the entry was the last one
+    pub fn indent(&self, is_last: bool) -> &'static str {
+        if is_last {
+            return "    ";
+        }
+
+        match self {
+            Charset::Ascii => "|   ",
+            Charset::Unicode | Charset::Rounded => "│   ",
+            Charset::Heavy => "┃   ",
+            Charset::Double => "║   ",
+        }
+    }
+
+    /// Bar filled to a fraction of its width, Unicode bars fill eighths of a cell
+    pub fn bar(&self, fraction: f64, width: usize) -> String {
+        let fraction = fraction.clamp(0.0, 1.0);
+
+        let (bar, filled_width) = match self {
+            Charset::Ascii => {
+                let filled = (fraction * width as f64).round() as usize;
+                ("#".repeat(filled), filled)
+            }
+            _ => {
+                let eighths = (fraction * (width * 8) as f64).round() as usize;
+                let partial = PARTIAL_BLOCKS[eighths % 8];
+                (
+                    format!("{}{}", "█".repeat(eighths / 8), partial),
+                    eighths / 8 + usize::from(!partial.is_empty()),
+                )
+            }
+        };
+
+        format!("{}{}", bar, " ".repeat(width - filled_width))
+    }
+
+    pub fn ellipsis(&self) -> &'static str {
+        match self {
+            Charset::Ascii => "...",
+            _ => "…",
+        }
+    }
+}
+
+/// Bar filled to the share of `size` in `total` in the current charset, with the percentage
+pub fn percentage_bar(size: u64, total: u64, width: usize) -> (String, f64) {
+    let fraction = if total == 0 {
+        0.0
+    } else {
+        size as f64 / total as f64
+    };
+
+    (Charset::current().bar(fraction, width), fraction * 100.0)
+}
+
+pub fn set_charset(charset: Charset) {
+    let _ = CHARSET.set(charset);
+}
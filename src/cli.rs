@@ -10,6 +10,14 @@ pub struct Cli {
     pub version: String,
     pub commands: Vec<Command>,
     pub default_command: Option<String>,
+    /// Arguments accepted by every command
+    pub global_args: Vec<Arg>,
+}
+
+impl Default for Cli {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl Cli {
@@ -23,6 +31,7 @@ impl Cli {
             version: env!("CARGO_PKG_VERSION").to_string(),
             commands: Vec::new(),
             default_command: None,
+            global_args: Vec::new(),
         }
     }
 
@@ -68,6 +77,30 @@ impl Cli {
         self
     }
 
+    /// Add an argument that can be passed to any command
+    pub fn with_global_arg(mut self, arg: Arg) -> Cli {
+        self.global_args.push(arg);
+        self
+    }
+
+    /// Get the value of a global argument
+    pub fn get_global_arg(&self, arg_name: &str) -> Value {
+        self.global_args
+            .iter()
+            .find(|&arg| arg.name == arg_name)
+            .and_then(arg_value)
+            .map(Value::Present)
+            .unwrap_or(Value::Missing(format!("{} could not be found", arg_name)))
+    }
+
+    /// Check if a global flag was passed
+    pub fn has_global_arg(&self, arg_name: &str) -> bool {
+        self.global_args
+            .iter()
+            .find(|&arg| arg.name == arg_name)
+            .is_some_and(|arg| env::args().any(|s| arg.matches(&s)))
+    }
+
     /// Set the default command to be run if no command is specified, if no default command is set the help command will be run
     pub fn with_default_command(mut self, default_command: &str) -> Cli {
         self.default_command = Some(default_command.to_string());
@@ -77,7 +110,6 @@ impl Cli {
     /// Match the command being passed in the arguments
     pub fn match_commands(&self) -> &Command {
         let args: Vec<String> = env::args().collect();
-        if args.contains(&"--help".to_string()) || args.contains(&"-h".to_string()) {}
 
         if args.len() <= 1 {
             if self.default_command.is_some() {
@@ -153,17 +185,11 @@ impl Command {
         self
     }
 
-    fn check_if_required_args_are_present(&self, env_args: &Vec<String>, arg: &Arg) {
-        if arg.requires.is_some() {
-            for required in arg.requires.as_ref().unwrap() {
+    fn check_if_required_args_are_present(&self, env_args: &[String], arg: &Arg) {
+        if let Some(requires) = &arg.requires {
+            for required in requires {
                 let required_arg = self.find_arg(required).unwrap();
-                if !env_args
-                    .iter()
-                    .any(|s| *s == format!("-{}", required_arg.short))
-                    && !env_args
-                        .iter()
-                        .any(|s| *s == format!("--{}", required_arg.long))
-                {
+                if !env_args.iter().any(|s| required_arg.matches(s)) {
                     println!(
                         "The argument \"{}\" requires the argument \"{}\"",
                         arg.name, required
@@ -185,9 +211,7 @@ impl Command {
             .find(|&arg| arg.name == arg_name)
             .map(|arg| {
                 let args: Vec<String> = env::args().collect();
-                let found = args
-                    .iter()
-                    .any(|s| *s == format!("-{}", arg.short) || *s == format!("--{}", arg.long));
+                let found = args.iter().any(|s| arg.matches(s));
 
                 if found {
                     self.check_if_required_args_are_present(&args, arg);
@@ -216,6 +240,11 @@ impl Command {
 
             let value = args[1 + (index + 1)].to_string();
 
+            // A flag in the location of an optional value means the value was left out
+            if value.starts_with('-') && !option.required {
+                return Value::Missing(format!("{} was not specified", option_name));
+            }
+
             if value == "--" && option.required {
                 return Value::Missing(format!(
                     "{} is a required value and must be specified",
@@ -226,10 +255,10 @@ impl Command {
             return Value::Present(value);
         }
 
-        return Value::Missing(format!(
+        Value::Missing(format!(
             "{} is a required value and must be specified",
             option_name
-        ));
+        ))
     }
 
     /// Get the value of an argument
@@ -237,20 +266,34 @@ impl Command {
         self.args
             .iter()
             .find(|&arg| arg.name == arg_name)
-            .and_then(|arg| {
-                let args: Vec<String> = env::args().collect();
-                let arg_index = args.iter().position(|s| {
-                    *s == format!("-{}", arg.short) || *s == format!("--{}", arg.long)
-                });
-
-                let value = arg_index.and_then(|index| args.get(index + 1));
-                value
-                    .or_else(|| arg.default_value.as_ref())
-                    .map(|s| s.to_string())
-            })
-            .map(|value| Value::Present(value))
+            .and_then(arg_value)
+            .map(Value::Present)
             .unwrap_or(Value::Missing(format!("{} could not be found", arg_name)))
     }
+
+    /// Get every value of an argument that can be passed more than once
+    pub fn get_args(&self, arg_name: &str) -> Vec<String> {
+        let arg = match self.find_arg(arg_name) {
+            Some(arg) => arg,
+            None => return Vec::new(),
+        };
+
+        let args: Vec<String> = env::args().collect();
+        args.iter()
+            .enumerate()
+            .filter(|(_, s)| arg.matches(s))
+            .filter_map(|(index, _)| args.get(index + 1).cloned())
+            .collect()
+    }
+}
+
+/// Value following an argument in the command line, or its default value if it is not there
+fn arg_value(arg: &Arg) -> Option<String> {
+    let args: Vec<String> = env::args().collect();
+    let arg_index = args.iter().position(|s| arg.matches(s));
+
+    let value = arg_index.and_then(|index| args.get(index + 1));
+    value.or(arg.default_value.as_ref()).map(|s| s.to_string())
 }
 
 pub struct CmdOption {
@@ -279,7 +322,7 @@ impl CmdOption {
 pub struct Arg {
     pub name: String,
     pub description: String,
-    pub short: char,
+    pub short: Option<char>,
     pub long: String,
     pub value_name: Option<String>,
     pub default_value: Option<String>,
@@ -292,7 +335,7 @@ impl Arg {
         Arg {
             name: name.to_string(),
             description: description.to_string(),
-            short,
+            short: Some(short),
             long: long.to_string(),
             value_name: None,
             default_value: None,
@@ -301,6 +344,24 @@ impl Arg {
         }
     }
 
+    /// Create an argument that can only be passed with its long name
+    pub fn long_only(name: &str, description: &str, long: &str) -> Arg {
+        Arg {
+            short: None,
+            ..Arg::new(name, description, long, ' ')
+        }
+    }
+
+    /// Check if a command line argument refers to this argument
+    fn matches(&self, arg: &str) -> bool {
+        match arg.strip_prefix("--") {
+            Some(long) => !self.long.is_empty() && long == self.long,
+            None => self
+                .short
+                .is_some_and(|short| arg.strip_prefix('-') == Some(short.encode_utf8(&mut [0; 4]))),
+        }
+    }
+
     /// Set the value name of the argument, used in the help menu to show the argument takes an input
     pub fn with_value_name(mut self, value_name: &str) -> Arg {
         self.value_name = Some(value_name.to_string());
@@ -315,10 +376,9 @@ impl Arg {
 
     /// Set the argument to require another argument to be present
     pub fn requires(mut self, requires: &str) -> Arg {
-        if self.requires.is_none() {
-            self.requires = Some(vec![requires.to_string()])
-        } else {
-            self.requires.as_mut().unwrap().push(requires.to_string());
+        match self.requires.as_mut() {
+            Some(existing) => existing.push(requires.to_string()),
+            None => self.requires = Some(vec![requires.to_string()]),
         }
         self
     }
@@ -356,7 +416,19 @@ impl Value {
 }
 
 fn cli_help(cli: &Cli, command_name: Option<&str>) {
-    if command_name.is_none() {
+    if let Some(command_name) = command_name {
+        let command_name: Vec<&str> = command_name.split(":").collect();
+
+        let command = cli
+            .commands
+            .iter()
+            .find(|command| command.name == command_name[0]);
+
+        match command {
+            Some(command) => command_help(command, 0),
+            None => cli.help(None),
+        }
+    } else {
         println!("{} {}", cli.name, cli.version);
         println!("{}", cli.description);
         println!("Author: {}", cli.author);
@@ -370,22 +442,14 @@ fn cli_help(cli: &Cli, command_name: Option<&str>) {
         for command in &cli.commands {
             command_help(command, 1);
         }
-    } else {
-        let command_name: Vec<&str> = command_name.unwrap().split(":").collect();
 
-        let command = cli
-            .commands
-            .iter()
-            .find(|command| command.name == command_name[0]);
-
-        if command.is_none() {
-            cli.help(None);
-            return;
+        if !cli.global_args.is_empty() {
+            prints!("[style:bold]GLOBAL OPTIONS:");
+            for arg in &cli.global_args {
+                arg_help(arg, 0);
+            }
+            println!();
         }
-
-        let command = command.unwrap();
-
-        command_help(command, 0)
     }
 }
 
@@ -411,20 +475,26 @@ fn command_help(command: &Command, indent: u8) {
         )
     }
     for arg in &command.args {
-        println!(
-            "    {}-{:<12} --{:<12} {:<14}{:<12}{}",
-            "    ".repeat((indent) as usize),
-            arg.short,
-            arg.long,
-            if arg.value_name.is_some() {
-                format!("<{}>", arg.value_name.as_ref().unwrap())
-            } else {
-                "".to_string()
-            },
-            if arg.required { " (required)" } else { "" },
-            arg.description,
-        );
+        arg_help(arg, indent);
     }
 
     println!();
 }
+
+fn arg_help(arg: &Arg, indent: u8) {
+    println!(
+        "    {}{:<13} --{:<12} {:<14}{:<12}{}",
+        "    ".repeat((indent) as usize),
+        match arg.short {
+            Some(short) => format!("-{}", short),
+            None => "".to_string(),
+        },
+        arg.long,
+        match &arg.value_name {
+            Some(value_name) => format!("<{}>", value_name),
+            None => "".to_string(),
+        },
+        if arg.required { " (required)" } else { "" },
+        arg.description,
+    );
+}
@@ -0,0 +1,239 @@
+use crate::metadata::EntryMetadata;
+use crate::scan::{DirectoryObject, FileObject};
+use std::collections::HashMap;
+use std::env;
+use std::fs;
+
+const ARCHIVE_STYLE: &str = "01;31";
+const IMAGE_AND_VIDEO_STYLE: &str = "01;35";
+const AUDIO_STYLE: &str = "00;36";
+
+const ARCHIVE_EXTENSIONS: &[&str] = &[
+    "tar", "tgz", "arc", "arj", "taz", "lha", "lz4", "lzh", "lzma", "tlz", "txz", "tzo", "t7z",
+    "zip", "z", "dz", "gz", "lrz", "lz", "lzo", "xz", "zst", "tzst", "bz2", "bz", "tbz", "tbz2",
+    "tz", "deb", "rpm", "jar", "war", "ear", "sar", "rar", "alz", "ace", "zoo", "cpio", "7z", "rz",
+    "cab", "wim", "swm", "dwm", "esd", "dmg", "iso",
+];
+const IMAGE_AND_VIDEO_EXTENSIONS: &[&str] = &[
+    "jpg", "jpeg", "mjpg", "mjpeg", "gif", "bmp", "pbm", "pgm", "ppm", "tga", "xbm", "xpm", "tif",
+    "tiff", "png", "svg", "svgz", "mng", "pcx", "mov", "mpg", "mpeg", "m2v", "mkv", "webm", "webp",
+    "ogm", "mp4", "m4v", "mp4v", "vob", "qt", "nuv", "wmv", "asf", "rm", "rmvb", "flc", "avi",
+    "fli", "flv", "gl", "dl", "xcf", "xwd", "yuv", "cgm", "emf", "ogv", "ogx", "heic", "avif",
+    "ico",
+];
+const AUDIO_EXTENSIONS: &[&str] = &[
+    "aac", "au", "flac", "m4a", "mid", "midi", "mka", "mp3", "mpc", "ogg", "ra", "wav", "oga",
+    "opus", "spx", "xspf",
+];
+
+/// Styles for file names in the format of the LS_COLORS environment variable used by ls,
+/// each style is a list of SGR codes like 01;34
+pub struct LsColors {
+    /// Styles for kinds of entries, keyed by the two letter codes of LS_COLORS like di or ex
+    kinds: HashMap<String, String>,
+    /// Styles for names ending in a suffix, the suffixes are lowercase
+    suffixes: Vec<(String, String)>,
+}
+
+/// Styles of the dircolors palette for kinds of entries
+const DIRCOLORS_KINDS: &[(&str, &str)] = &[
+    ("di", "01;34"),
+    ("ln", "01;36"),
+    ("pi", "40;33"),
+    ("so", "01;35"),
+    ("bd", "40;33;01"),
+    ("cd", "40;33;01"),
+    ("or", "40;31;01"),
+    ("su", "37;41"),
+    ("sg", "30;43"),
+    ("tw", "30;42"),
+    ("ow", "34;42"),
+    ("st", "37;44"),
+    ("ex", "01;32"),
+];
+
+/// Groups of extensions that share a style in the dircolors palette
+#[derive(Debug, Clone, Copy)]
+pub enum ExtensionGroup {
+    Archive,
+    ImageAndVideo,
+    Audio,
+}
+
+impl ExtensionGroup {
+    pub fn extensions(&self) -> &'static [&'static str] {
+        match self {
+            ExtensionGroup::Archive => ARCHIVE_EXTENSIONS,
+            ExtensionGroup::ImageAndVideo => IMAGE_AND_VIDEO_EXTENSIONS,
+            ExtensionGroup::Audio => AUDIO_EXTENSIONS,
+        }
+    }
+}
+
+impl Default for LsColors {
+    /// The palette of dircolors, used when LS_COLORS is not set
+    fn default() -> Self {
+        let mut colors = LsColors::empty();
+        for (kind, style) in DIRCOLORS_KINDS {
+            colors.set_kind(kind, style);
+        }
+
+        for (group, style) in [
+            (ExtensionGroup::Archive, ARCHIVE_STYLE),
+            (ExtensionGroup::ImageAndVideo, IMAGE_AND_VIDEO_STYLE),
+            (ExtensionGroup::Audio, AUDIO_STYLE),
+        ] {
+            colors.set_group(group, style);
+        }
+
+        colors
+    }
+}
+
+impl LsColors {
+    /// Read LS_COLORS, falling back to the default palette if it is not set
+    pub fn from_env() -> LsColors {
+        match env::var("LS_COLORS") {
+            Ok(value) if !value.is_empty() => LsColors::parse(&value),
+            _ => LsColors::default(),
+        }
+    }
+
+    /// A palette without any styles, names are shown in the default color of the terminal
+    pub fn empty() -> LsColors {
+        LsColors {
+            kinds: HashMap::new(),
+            suffixes: Vec::new(),
+        }
+    }
+
+    pub fn parse(value: &str) -> LsColors {
+        let mut colors = LsColors::empty();
+
+        for entry in value.split(':') {
+            let (key, style) = match entry.split_once('=') {
+                Some(pair) => pair,
+                None => continue,
+            };
+
+            match key.strip_prefix('*') {
+                Some(suffix) => colors
+                    .suffixes
+                    .push((suffix.to_lowercase(), style.to_string())),
+                None => {
+                    colors.kinds.insert(key.to_string(), style.to_string());
+                }
+            }
+        }
+
+        // Later entries win, like in ls
+        colors.suffixes.reverse();
+        colors
+    }
+
+    /// Set the style of a kind of entry, using the two letter codes of LS_COLORS
+    pub fn set_kind(&mut self, kind: &str, style: &str) {
+        self.kinds.insert(kind.to_string(), style.to_string());
+    }
+
+    /// Set the style of names ending in a suffix like .rs, taking priority over earlier suffixes
+    pub fn set_suffix(&mut self, suffix: &str, style: &str) {
+        let suffix = suffix.to_lowercase();
+        self.suffixes.retain(|(existing, _)| *existing != suffix);
+        self.suffixes.insert(0, (suffix, style.to_string()));
+    }
+
+    pub fn set_group(&mut self, group: ExtensionGroup, style: &str) {
+        for extension in group.extensions() {
+            self.set_suffix(&format!(".{}", extension), style);
+        }
+    }
+
+    fn kind(&self, kind: &str) -> Option<&str> {
+        self.kinds
+            .get(kind)
+            .map(|style| style.as_str())
+            .filter(|style| !style.is_empty() && *style != "0" && *style != "00")
+    }
+
+    pub fn file_style(&self, file: &FileObject) -> Option<&str> {
+        if let Some(style) = file
+            .metadata
+            .as_ref()
+            .and_then(|metadata| self.special_file_style(&file.path, metadata))
+        {
+            return Some(style);
+        }
+
+        let name = file.full_name.to_lowercase();
+        self.suffixes
+            .iter()
+            .find(|(suffix, _)| name.ends_with(suffix.as_str()))
+            .map(|(_, style)| style.as_str())
+            .or_else(|| self.kind("fi"))
+    }
+
+    /// Style for symbolic links, devices and executables, which take priority over the
+    /// style of the extension
+    fn special_file_style(&self, path: &str, metadata: &EntryMetadata) -> Option<&str> {
+        if let Some(attributes) = metadata.attributes {
+            // Reparse points are how Windows represents symbolic links
+            if attributes & 0x400 != 0 {
+                return self.kind("ln");
+            }
+        }
+
+        let mode = metadata.mode?;
+        match mode & 0o170000 {
+            0o120000 if fs::metadata(path).is_err() => self.kind("or").or(self.kind("ln")),
+            0o120000 => self.kind("ln"),
+            0o010000 => self.kind("pi"),
+            0o140000 => self.kind("so"),
+            0o060000 => self.kind("bd"),
+            0o020000 => self.kind("cd"),
+            _ if mode & 0o4000 != 0 => self.kind("su"),
+            _ if mode & 0o2000 != 0 => self.kind("sg"),
+            _ if mode & 0o111 != 0 => self.kind("ex"),
+            _ => None,
+        }
+    }
+
+    pub fn directory_style(&self, directory: &DirectoryObject) -> Option<&str> {
+        let metadata = directory.metadata.as_ref();
+
+        if let Some(mode) = metadata.and_then(|metadata| metadata.mode) {
+            if mode & 0o170000 == 0o120000 {
+                return self.kind("ln");
+            }
+
+            let is_sticky = mode & 0o1000 != 0;
+            let is_other_writable = mode & 0o002 != 0;
+            let style = match (is_sticky, is_other_writable) {
+                (true, true) => self.kind("tw"),
+                (false, true) => self.kind("ow"),
+                (true, false) => self.kind("st"),
+                (false, false) => None,
+            };
+            if style.is_some() {
+                return style;
+            }
+        }
+
+        if metadata
+            .and_then(|metadata| metadata.attributes)
+            .is_some_and(|attributes| attributes & 0x400 != 0)
+        {
+            return self.kind("ln");
+        }
+
+        self.kind("di")
+    }
+}
+
+/// Wrap text in the escape codes of an LS_COLORS style
+pub fn paint(style: Option<&str>, text: &str) -> String {
+    match style {
+        Some(style) => format!("\u{001b}[{}m{}\u{001b}[0m", style, text),
+        None => text.to_string(),
+    }
+}
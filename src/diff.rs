@@ -0,0 +1,321 @@
+use crate::charset::Charset;
+use crate::json::JsonValue;
+use crate::scan::{DirectoryObject, FileObject};
+use crate::size::bytes_to_best_size;
+use crate::styles::Color;
+use std::collections::BTreeMap;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Change {
+    Added,
+    Removed,
+    Modified,
+    Unchanged,
+}
+
+impl Change {
+    fn marker(&self) -> char {
+        match self {
+            Change::Added => '+',
+            Change::Removed => '-',
+            Change::Modified => '~',
+            Change::Unchanged => ' ',
+        }
+    }
+
+    fn color(&self) -> Color {
+        match self {
+            Change::Added => Color::Green,
+            Change::Removed => Color::Red,
+            Change::Modified => Color::Yellow,
+            Change::Unchanged => Color::Reset,
+        }
+    }
+
+    pub fn get_name(&self) -> &str {
+        match self {
+            Change::Added => "added",
+            Change::Removed => "removed",
+            Change::Modified => "modified",
+            Change::Unchanged => "unchanged",
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct FileDiff {
+    pub name: String,
+    pub change: Change,
+    pub old_size: u64,
+    pub new_size: u64,
+}
+
+#[derive(Debug)]
+pub struct DirectoryDiff {
+    pub name: String,
+    pub change: Change,
+    pub old_size: u64,
+    pub new_size: u64,
+    pub files: Vec<FileDiff>,
+    pub directories: Vec<DirectoryDiff>,
+}
+
+impl DirectoryDiff {
+    pub fn size_delta(&self) -> i64 {
+        self.new_size as i64 - self.old_size as i64
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct DiffSummary {
+    pub added: u64,
+    pub removed: u64,
+    pub modified: u64,
+}
+
+/// Merge two trees into a single tree marking what changed between them
+pub fn diff_trees(old: &DirectoryObject, new: &DirectoryObject) -> DirectoryDiff {
+    diff_directories(&new.base_name(), Some(old), Some(new))
+}
+
+fn diff_directories(
+    name: &str,
+    old: Option<&DirectoryObject>,
+    new: Option<&DirectoryObject>,
+) -> DirectoryDiff {
+    let mut files: BTreeMap<&str, (Option<&FileObject>, Option<&FileObject>)> = BTreeMap::new();
+    let mut directories: BTreeMap<String, (Option<&DirectoryObject>, Option<&DirectoryObject>)> =
+        BTreeMap::new();
+
+    if let Some(old) = old {
+        for file in &old.files {
+            files.entry(&file.full_name).or_default().0 = Some(file);
+        }
+        for directory in &old.directories {
+            directories.entry(directory.base_name()).or_default().0 = Some(directory);
+        }
+    }
+
+    if let Some(new) = new {
+        for file in &new.files {
+            files.entry(&file.full_name).or_default().1 = Some(file);
+        }
+        for directory in &new.directories {
+            directories.entry(directory.base_name()).or_default().1 = Some(directory);
+        }
+    }
+
+    let files: Vec<FileDiff> = files
+        .into_iter()
+        .map(|(name, (old, new))| {
+            let old_size = old.map_or(0, |file| file.size);
+            let new_size = new.map_or(0, |file| file.size);
+            let change = match (old, new) {
+                (None, _) => Change::Added,
+                (_, None) => Change::Removed,
+                _ if old_size != new_size => Change::Modified,
+                _ => Change::Unchanged,
+            };
+
+            FileDiff {
+                name: name.to_string(),
+                change,
+                old_size,
+                new_size,
+            }
+        })
+        .collect();
+
+    let directories: Vec<DirectoryDiff> = directories
+        .into_iter()
+        .map(|(name, (old, new))| diff_directories(&name, old, new))
+        .collect();
+
+    let old_size = old.map_or(0, |directory| directory.total_size());
+    let new_size = new.map_or(0, |directory| directory.total_size());
+
+    let has_changes = files.iter().any(|file| file.change != Change::Unchanged)
+        || directories
+            .iter()
+            .any(|directory| directory.change != Change::Unchanged);
+
+    let change = match (old, new) {
+        (None, _) => Change::Added,
+        (_, None) => Change::Removed,
+        _ if has_changes || old_size != new_size => Change::Modified,
+        _ => Change::Unchanged,
+    };
+
+    DirectoryDiff {
+        name: name.to_string(),
+        change,
+        old_size,
+        new_size,
+        files,
+        directories,
+    }
+}
+
+pub fn summarize(diff: &DirectoryDiff) -> DiffSummary {
+    let mut summary = DiffSummary::default();
+    add_to_summary(diff, &mut summary);
+    summary
+}
+
+fn add_to_summary(diff: &DirectoryDiff, summary: &mut DiffSummary) {
+    for file in &diff.files {
+        match file.change {
+            Change::Added => summary.added += 1,
+            Change::Removed => summary.removed += 1,
+            Change::Modified => summary.modified += 1,
+            Change::Unchanged => {}
+        }
+    }
+
+    for directory in &diff.directories {
+        add_to_summary(directory, summary);
+    }
+}
+
+pub fn format_size_delta(delta: i64) -> String {
+    let sign = if delta < 0 { "-" } else { "+" };
+    format!("{}{}", sign, bytes_to_best_size(delta.unsigned_abs()))
+}
+
+pub fn display_diff(diff: &DirectoryDiff, show_unchanged: bool) {
+    println!("{} ({})", diff.name, format_size_delta(diff.size_delta()));
+    display_diff_tree(diff, "", show_unchanged);
+
+    let summary = summarize(diff);
+    println!();
+    println!(
+        "{} added, {} removed, {} modified, {} total",
+        summary.added,
+        summary.removed,
+        summary.modified,
+        format_size_delta(diff.size_delta())
+    );
+}
+
+fn display_diff_tree(diff: &DirectoryDiff, indent: &str, show_unchanged: bool) {
+    let files: Vec<&FileDiff> = diff
+        .files
+        .iter()
+        .filter(|file| show_unchanged || file.change != Change::Unchanged)
+        .collect();
+    let directories: Vec<&DirectoryDiff> = diff
+        .directories
+        .iter()
+        .filter(|directory| show_unchanged || directory.change != Change::Unchanged)
+        .collect();
+
+    for (i, file) in files.iter().enumerate() {
+        let is_last_file = i == files.len() - 1 && directories.is_empty();
+        let sizes = match file.change {
+            Change::Added => bytes_to_best_size(file.new_size),
+            Change::Removed | Change::Unchanged => bytes_to_best_size(file.old_size),
+            Change::Modified => format!(
+                "{} -> {}, {}",
+                bytes_to_best_size(file.old_size),
+                bytes_to_best_size(file.new_size),
+                format_size_delta(file.new_size as i64 - file.old_size as i64)
+            ),
+        };
+
+        println!(
+            "{}{}{}{} {} ({}){}",
+            indent,
+            Charset::current().branch(is_last_file),
+            file.change.color().to_ansi_color_string(),
+            file.change.marker(),
+            file.name,
+            sizes,
+            Color::Reset.to_ansi_color_string()
+        );
+    }
+
+    for (i, directory) in directories.iter().enumerate() {
+        let is_last_directory = i == directories.len() - 1;
+
+        println!(
+            "{}{}{}{} {} ({}){}",
+            indent,
+            Charset::current().branch(is_last_directory),
+            directory.change.color().to_ansi_color_string(),
+            directory.change.marker(),
+            directory.name,
+            format_size_delta(directory.size_delta()),
+            Color::Reset.to_ansi_color_string()
+        );
+
+        let child_indent = format!("{}{}", indent, Charset::current().indent(is_last_directory));
+        display_diff_tree(directory, &child_indent, show_unchanged);
+    }
+}
+
+pub fn diff_to_json(diff: &DirectoryDiff, old_source: &str, new_source: &str) -> JsonValue {
+    let summary = summarize(diff);
+    let mut changes = Vec::new();
+    collect_changes(diff, "", &mut changes);
+
+    JsonValue::object()
+        .with("old", old_source.into())
+        .with("new", new_source.into())
+        .with(
+            "summary",
+            JsonValue::object()
+                .with("added", summary.added.into())
+                .with("removed", summary.removed.into())
+                .with("modified", summary.modified.into())
+                .with("old_size", diff.old_size.into())
+                .with("new_size", diff.new_size.into())
+                .with("size_delta", diff.size_delta().into()),
+        )
+        .with("changes", changes.into())
+}
+
+fn collect_changes(diff: &DirectoryDiff, parent_path: &str, changes: &mut Vec<JsonValue>) {
+    for file in &diff.files {
+        if file.change == Change::Unchanged {
+            continue;
+        }
+        changes.push(change_to_json(
+            &format!("{}{}", parent_path, file.name),
+            "file",
+            file.change,
+            file.old_size,
+            file.new_size,
+        ));
+    }
+
+    for directory in &diff.directories {
+        if directory.change == Change::Unchanged {
+            continue;
+        }
+        let path = format!("{}{}/", parent_path, directory.name);
+        changes.push(change_to_json(
+            &path,
+            "directory",
+            directory.change,
+            directory.old_size,
+            directory.new_size,
+        ));
+        collect_changes(directory, &path, changes);
+    }
+}
+
+fn change_to_json(
+    path: &str,
+    object_type: &str,
+    change: Change,
+    old_size: u64,
+    new_size: u64,
+) -> JsonValue {
+    JsonValue::object()
+        .with("path", path.into())
+        .with("type", object_type.into())
+        .with("change", change.get_name().into())
+        .with("old_size", old_size.into())
+        .with("new_size", new_size.into())
+        .with("size_delta", (new_size as i64 - old_size as i64).into())
+}
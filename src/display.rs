@@ -1,17 +1,222 @@
-use crate::scan::DirectoryObject;
-use crate::utils::get_current_directory_path;
+use crate::charset::{percentage_bar, Charset};
+use crate::colors::paint;
+use crate::icons::Icons;
+use crate::loc::format_line_counts;
+use crate::metadata::{format_time, EntryMetadata, OwnerNames, TimeField};
+use crate::scan::{DirectoryObject, FileObject};
+use crate::size::bytes_to_best_size;
+use crate::styles::{Color, TextStyle};
+use crate::system::terminal::terminal_size;
+use crate::theme::Theme;
+use crate::utils::{
+    display_width, format_count, get_current_directory_path, pad, relative_path, shell_quote,
+    truncate_middle,
+};
 use crate::OS;
+use std::collections::{HashMap, HashSet};
 use std::fs;
+use std::io::{self, IsTerminal};
 use std::path::PathBuf;
 
-pub fn display(
-    tree: &DirectoryObject,
-    show_endings: &bool,
-    show_file_sizes: &bool,
-    show_directory_sizes: &bool,
-    show_file_counts_in_directories: &bool,
-    os: &OS,
-) {
+/// Names are never truncated below this width, even if the line does not fit
+const MIN_NAME_WIDTH: usize = 10;
+
+/// Width of the bars shown by `size_bars`
+const BAR_WIDTH: usize = 10;
+
+/// What the size bars of entries are relative to
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum BarScale {
+    /// The cumulative size of the directory the entry is in
+    Parent,
+    /// The cumulative size of the scanned directory
+    Root,
+}
+
+impl BarScale {
+    pub fn from_string(string: &str) -> Option<BarScale> {
+        match string {
+            "parent" => Some(BarScale::Parent),
+            "root" => Some(BarScale::Root),
+            _ => None,
+        }
+    }
+}
+
+/// How entries are named in the tree
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum PathMode {
+    Name,
+    /// Paths relative to the scanned directory, like tree -f
+    Relative,
+    Absolute,
+}
+
+pub struct DisplayOptions {
+    pub show_endings: bool,
+    pub show_file_sizes: bool,
+    pub show_directory_sizes: bool,
+    pub show_file_counts_in_directories: bool,
+    pub show_line_counts: bool,
+    pub show_permissions: bool,
+    pub show_owner: bool,
+    pub show_group: bool,
+    pub show_date: bool,
+    /// Which of the entry times is shown by `show_date`
+    pub time_field: TimeField,
+    pub time_format: String,
+    /// Most entries shown per directory, the rest are summarized on a single line
+    pub max_entries: Option<usize>,
+    /// Whether entries are shown by name or by path
+    pub path_mode: PathMode,
+    /// Print one path per line instead of a tree
+    pub list: bool,
+    /// Show a bar with the share of the size of each entry, relative to its parent or the root
+    pub size_bars: Option<BarScale>,
+    /// Columns the tree may take up, the width of the terminal is used if not set
+    pub width: Option<usize>,
+    /// Styles for names, sizes and tree lines, output is plain text if not set
+    pub colors: Option<Theme>,
+    /// Icons shown before names, no icons are shown if not set
+    pub icons: Option<Icons>,
+    pub highlighted_paths: HashSet<String>,
+    /// Extra text shown after an entry, keyed by the path of the entry
+    pub annotations: HashMap<String, String>,
+}
+
+impl DisplayOptions {
+    fn annotation(&self, path: &str) -> &str {
+        self.annotations
+            .get(path)
+            .map_or("", |annotation| annotation)
+    }
+
+    /// Metadata shown in brackets before the name of an entry, empty if no columns are enabled
+    fn metadata_columns(
+        &self,
+        metadata: Option<&EntryMetadata>,
+        owner_names: &mut OwnerNames,
+    ) -> Vec<String> {
+        let mut columns = Vec::new();
+
+        if self.show_permissions {
+            columns
+                .push(metadata.map_or("?".to_string(), |metadata| metadata.permissions_string()));
+        }
+
+        if self.show_owner {
+            columns.push(match metadata.and_then(|metadata| metadata.uid) {
+                Some(uid) => owner_names.user_name(uid),
+                None => "?".to_string(),
+            });
+        }
+
+        if self.show_group {
+            columns.push(match metadata.and_then(|metadata| metadata.gid) {
+                Some(gid) => owner_names.group_name(gid),
+                None => "?".to_string(),
+            });
+        }
+
+        if self.show_date {
+            columns.push(
+                match metadata.and_then(|metadata| metadata.get_time(self.time_field)) {
+                    Some(time) => format_time(time, &self.time_format),
+                    None => "?".to_string(),
+                },
+            );
+        }
+
+        columns
+    }
+
+    /// Bar and percentage of the share of a size, empty if size bars are disabled
+    fn size_bar(&self, size: u64, parent_size: u64, root_size: u64) -> String {
+        let total = match self.size_bars {
+            Some(BarScale::Parent) => parent_size,
+            Some(BarScale::Root) => root_size,
+            None => return String::new(),
+        };
+
+        let (bar, percentage) = percentage_bar(size, total, BAR_WIDTH);
+        format!("{} {:5.1}%", bar, percentage)
+    }
+
+    /// Path of an entry in the relative and absolute path modes, `None` if only names are shown
+    fn entry_path(&self, path: &str, root: &Root) -> Option<String> {
+        match self.path_mode {
+            PathMode::Name => None,
+            PathMode::Relative => Some(relative_path(&root.path, path)),
+            PathMode::Absolute => Some(
+                root.absolute_path
+                    .join(relative_path(&root.path, path))
+                    .to_string_lossy()
+                    .to_string(),
+            ),
+        }
+    }
+
+    /// Name or path of a file, without its extension if endings are hidden
+    fn file_name(&self, file: &FileObject, root: &Root) -> String {
+        let path = match self.entry_path(&file.path, root) {
+            Some(path) => path,
+            None if self.show_endings => return file.full_name.clone(),
+            None => return file.name.clone(),
+        };
+
+        if self.show_endings || file.ending.is_empty() {
+            return path;
+        }
+
+        match path.strip_suffix(&format!(".{}", file.ending)) {
+            Some(path) => path.to_string(),
+            None => path,
+        }
+    }
+
+    /// Name of an entry with its icon in front, if icons are enabled
+    fn with_icon<'a>(&'a self, icon: impl Fn(&'a Icons) -> &'a str, name: &str) -> String {
+        match self.icons.as_ref() {
+            Some(icons) => format!("{} {}", icon(icons), name),
+            None => name.to_string(),
+        }
+    }
+
+    /// Colors of an entry, highlighted entries are not colored so the highlight is not reset
+    fn colors_for(&self, path: &str) -> Option<&Theme> {
+        if self.highlighted_paths.contains(path) {
+            None
+        } else {
+            self.colors.as_ref()
+        }
+    }
+
+    /// Indentation and connector in front of an entry, styled so the names stand out
+    fn branch(&self, branch: &str) -> String {
+        paint(
+            self.colors
+                .as_ref()
+                .and_then(|theme| theme.connector.as_deref()),
+            branch,
+        )
+    }
+
+    fn highlight(&self, path: &str, text: String) -> String {
+        if self.highlighted_paths.contains(path) {
+            format!(
+                "{}{}{}{}",
+                TextStyle::Bold.to_ansi_style_string(),
+                Color::Green.to_ansi_color_string(),
+                text,
+                Color::Reset.to_ansi_color_string()
+            )
+        } else {
+            text
+        }
+    }
+}
+
+pub fn display(tree: &DirectoryObject, options: &DisplayOptions, os: &OS) {
     let display_name = if tree.name.is_empty() {
         // Handle relative paths
         let current_dir = PathBuf::from(get_current_directory_path());
@@ -26,55 +231,184 @@ pub fn display(
         tree.name.clone()
     };
 
+    let root = Root {
+        path: tree.path.clone(),
+        absolute_path: if options.path_mode == PathMode::Absolute {
+            let current_dir = PathBuf::from(get_current_directory_path());
+            fs::canonicalize(current_dir.join(&tree.path))
+                .unwrap_or_else(|_| PathBuf::from(&tree.path))
+        } else {
+            PathBuf::new()
+        },
+        size: tree.total_size(),
+    };
+
+    if options.list {
+        list_paths(tree, &root, options);
+        return;
+    }
+
+    let display_name = match options.path_mode {
+        PathMode::Absolute => root.absolute_path.to_string_lossy().to_string(),
+        _ => display_name,
+    };
+    let display_name = options.with_icon(|icons| icons.directory_icon(tree), &display_name);
+    let display_name = match options.colors.as_ref() {
+        Some(theme) => paint(theme.names.directory_style(tree), &display_name),
+        None => display_name,
+    };
+
     println!("{}", display_name);
 
-    display_tree(
-        &tree,
-        0,
+    let mut lines = Vec::new();
+    collect_lines(
+        tree,
         "",
-        show_endings,
-        show_file_sizes,
-        show_directory_sizes,
-        show_file_counts_in_directories,
+        options,
+        &mut OwnerNames::default(),
         os,
+        &root,
+        &mut lines,
     );
+
+    let width = options.width.or_else(|| {
+        if io::stdout().is_terminal() {
+            terminal_size().map(|(columns, _)| columns)
+        } else {
+            None
+        }
+    });
+    let widths = measure_lines(&lines, width);
+    for line in &lines {
+        print_line(line, &widths, width, options);
+    }
+}
+
+/// The scanned directory, which the paths and size bars of entries are relative to
+struct Root {
+    path: String,
+    /// Only resolved for `PathMode::Absolute`
+    absolute_path: PathBuf,
+    size: u64,
+}
+
+/// Print the path of every entry on its own line, quoted so other tools can read it back
+fn list_paths(directory: &DirectoryObject, root: &Root, options: &DisplayOptions) {
+    for file in &directory.files {
+        println!("{}", shell_quote(&options.file_name(file, root)));
+    }
+
+    for sub_directory in &directory.directories {
+        let path = options
+            .entry_path(&sub_directory.path, root)
+            .unwrap_or(sub_directory.name.clone());
+        println!("{}", shell_quote(&path));
+        list_paths(sub_directory, root, options);
+    }
+}
+
+/// A line of the tree, collected before anything is printed so the columns can be aligned
+struct TreeLine<'a> {
+    path: &'a str,
+    /// Indentation and connector in front of the entry
+    branch: String,
+    metadata: Vec<String>,
+    /// Name of the entry with its icon, or the summary of entries hidden by `max_entries`
+    name: String,
+    name_style: Option<&'a str>,
+    count: String,
+    size: String,
+    size_style: Option<&'a str>,
+    /// Bar and percentage of the share of the size of the entry
+    bar: String,
+    /// Line counts and annotations, shown after the aligned columns
+    details: String,
+}
+
+impl TreeLine<'_> {
+    fn has_columns(&self) -> bool {
+        !self.count.is_empty() || !self.size.is_empty() || !self.bar.is_empty()
+    }
 }
 
-fn display_tree(
-    tree: &DirectoryObject,
-    depth: i32,
+/// Widths of the aligned parts of the tree, measured over every line
+struct ColumnWidths {
+    metadata: Vec<usize>,
+    /// Column where the count and size columns start
+    name_end: usize,
+    count: usize,
+    size: usize,
+    bar: usize,
+}
+
+fn collect_lines<'a>(
+    tree: &'a DirectoryObject,
     indent: &str,
-    show_endings: &bool,
-    show_file_sizes: &bool,
-    show_directory_sizes: &bool,
-    show_file_counts_in_directories: &bool,
+    options: &'a DisplayOptions,
+    owner_names: &mut OwnerNames,
     os: &OS,
+    root: &Root,
+    lines: &mut Vec<TreeLine<'a>>,
 ) {
-    for (i, file) in tree.files.iter().enumerate() {
-        let file_display = format!(
-            "{} {}",
-            if *show_endings {
-                &file.full_name
-            } else {
-                &file.name
-            },
-            if *show_file_sizes {
+    let charset = Charset::current();
+    let parent_size = if options.size_bars.is_some() {
+        tree.total_size()
+    } else {
+        0
+    };
+    let mut files: Vec<&FileObject> = tree.files.iter().collect();
+    let mut directories: Vec<&DirectoryObject> = tree.directories.iter().collect();
+    // Entries come in the order they were read from disk, which differs between runs and
+    // systems, so they are sorted by name before choosing which ones to show
+    if options.max_entries.is_some() {
+        files.sort_by(|a, b| a.full_name.cmp(&b.full_name));
+        directories.sort_by(|a, b| a.name.cmp(&b.name));
+    }
+
+    let entry_count = files.len() + directories.len();
+    let shown_count = options
+        .max_entries
+        .map_or(entry_count, |max_entries| max_entries.min(entry_count));
+    let shown_files = files.len().min(shown_count);
+    let shown_directories = shown_count - shown_files;
+    let has_overflow = shown_count < entry_count;
+
+    for (i, file) in files.iter().take(shown_files).enumerate() {
+        let colors = options.colors_for(&file.path);
+        let is_last_file = i == shown_count - 1 && !has_overflow;
+
+        lines.push(TreeLine {
+            path: &file.path,
+            branch: format!("{}{}", indent, charset.branch(is_last_file)),
+            metadata: options.metadata_columns(file.metadata.as_ref(), owner_names),
+            name: options.with_icon(
+                |icons| icons.file_icon(file),
+                &options.file_name(file, root),
+            ),
+            name_style: colors.and_then(|theme| theme.names.file_style(file)),
+            count: String::new(),
+            size: if options.show_file_sizes {
                 format!("({})", &file.size_string)
             } else {
-                "".to_string()
-            }
-        );
-
-        let is_last_file = i == tree.files.len() - 1 && tree.directories.is_empty();
-        println!(
-            "{}{}── {}",
-            indent,
-            if is_last_file { '└' } else { '├' },
-            file_display
-        );
+                String::new()
+            },
+            size_style: colors.and_then(|theme| theme.size_style(file.size)),
+            bar: options.size_bar(file.size, parent_size, root.size),
+            details: join_details(
+                match (&file.code_stats, options.show_line_counts) {
+                    (Some(code_stats), true) => format!(
+                        "({}: {})",
+                        code_stats.language,
+                        format_line_counts(&code_stats.lines)
+                    ),
+                    _ => String::new(),
+                },
+                options.annotation(&file.path),
+            ),
+        });
     }
 
-    for (i, directory) in tree.directories.iter().enumerate() {
+    for (i, directory) in directories.iter().take(shown_directories).enumerate() {
         let directory_name = match os {
             OS::Windows => directory
                 .name
@@ -85,39 +419,238 @@ fn display_tree(
             OS::Mac => directory.name.clone(),
         };
 
-        let directory_display = format!(
-            "{} {} {}",
-            directory_name,
-            if *show_file_counts_in_directories && directory.file_count > 0 {
+        let colors = options.colors_for(&directory.path);
+        let is_last_directory = i == shown_directories - 1 && !has_overflow;
+
+        lines.push(TreeLine {
+            path: &directory.path,
+            branch: format!("{}{}", indent, charset.branch(is_last_directory)),
+            metadata: options.metadata_columns(directory.metadata.as_ref(), owner_names),
+            name: options.with_icon(
+                |icons| icons.directory_icon(directory),
+                &options
+                    .entry_path(&directory.path, root)
+                    .unwrap_or(directory_name),
+            ),
+            name_style: colors.and_then(|theme| theme.names.directory_style(directory)),
+            count: if options.show_file_counts_in_directories && directory.file_count > 0 {
                 format!("({})", directory.file_count)
             } else {
-                "".to_string()
+                String::new()
             },
-            if *show_directory_sizes {
+            size: if options.show_directory_sizes {
                 format!("({})", directory.size_string)
             } else {
-                "".to_string()
-            }
-        );
-
-        let is_last_directory = i == tree.directories.len() - 1;
-        let connector = if is_last_directory { '└' } else { '├' };
+                String::new()
+            },
+            size_style: colors.and_then(|theme| theme.size_style(directory.size)),
+            bar: options.size_bar(directory.total_size(), parent_size, root.size),
+            details: join_details(
+                match (&directory.line_counts, options.show_line_counts) {
+                    (Some(line_counts), true) => format!("({})", format_line_counts(line_counts)),
+                    _ => String::new(),
+                },
+                options.annotation(&directory.path),
+            ),
+        });
 
-        println!("{}{}── {}", indent, connector, directory_display);
-        let child_indent = format!(
-            "{}{}",
-            indent,
-            if is_last_directory { "    " } else { "│   " }
-        );
-        display_tree(
-            &directory,
-            depth + 1,
+        let child_indent = format!("{}{}", indent, charset.indent(is_last_directory));
+        collect_lines(
+            directory,
             &child_indent,
-            show_endings,
-            show_file_sizes,
-            show_directory_sizes,
-            show_file_counts_in_directories,
+            options,
+            owner_names,
             os,
+            root,
+            lines,
+        );
+    }
+
+    if has_overflow {
+        lines.push(TreeLine {
+            path: "",
+            branch: format!("{}{}", indent, charset.branch(true)),
+            metadata: Vec::new(),
+            name: overflow_summary(&files[shown_files..], &directories[shown_directories..]),
+            name_style: None,
+            count: String::new(),
+            size: String::new(),
+            size_style: None,
+            bar: String::new(),
+            details: String::new(),
+        });
+    }
+}
+
+fn join_details(line_counts: String, annotation: &str) -> String {
+    [line_counts.as_str(), annotation]
+        .iter()
+        .filter(|detail| !detail.is_empty())
+        .cloned()
+        .collect::<Vec<&str>>()
+        .join(" ")
+}
+
+/// Width of the metadata in front of a name, like "[rw-r--r-- 2024-01-01 12:00]  "
+fn metadata_width(widths: &[usize]) -> usize {
+    if widths.is_empty() {
+        0
+    } else {
+        widths.iter().sum::<usize>() + widths.len() - 1 + 4
+    }
+}
+
+fn measure_lines(lines: &[TreeLine], width: Option<usize>) -> ColumnWidths {
+    let mut metadata = Vec::new();
+    let mut count = 0;
+    let mut size = 0;
+    let mut bar = 0;
+
+    for line in lines {
+        metadata.resize(metadata.len().max(line.metadata.len()), 0);
+        for (i, column) in line.metadata.iter().enumerate() {
+            metadata[i] = metadata[i].max(display_width(column));
+        }
+        count = count.max(display_width(&line.count));
+        size = size.max(display_width(&line.size));
+        bar = bar.max(display_width(&line.bar));
+    }
+
+    let name_end = lines
+        .iter()
+        .filter(|line| line.has_columns())
+        .map(|line| {
+            display_width(&line.branch) + metadata_width(&metadata) + display_width(&line.name)
+        })
+        .max()
+        .unwrap_or(0);
+
+    let columns_width = [count, size, bar]
+        .iter()
+        .filter(|width| **width > 0)
+        .map(|width| width + 1)
+        .sum::<usize>();
+
+    ColumnWidths {
+        name_end: match width {
+            Some(width) => name_end.min(width.saturating_sub(columns_width)),
+            None => name_end,
+        },
+        metadata,
+        count,
+        size,
+        bar,
+    }
+}
+
+fn print_line(
+    line: &TreeLine,
+    widths: &ColumnWidths,
+    width: Option<usize>,
+    options: &DisplayOptions,
+) {
+    let mut text = String::new();
+
+    if !line.metadata.is_empty() {
+        let columns: Vec<String> = line
+            .metadata
+            .iter()
+            .enumerate()
+            .map(|(i, column)| {
+                // Dates are right aligned so their digits line up, names are left aligned
+                let is_date = options.show_date && i == line.metadata.len() - 1;
+                pad(column, widths.metadata[i], is_date)
+            })
+            .collect();
+        text.push_str(&format!("[{}]  ", columns.join(" ")));
+    }
+
+    let prefix_width = display_width(&line.branch) + metadata_width(&widths.metadata);
+    let name_width = if line.has_columns() {
+        Some(widths.name_end.saturating_sub(prefix_width))
+    } else {
+        width.map(|width| width.saturating_sub(prefix_width))
+    };
+    let name = match name_width {
+        Some(name_width) => truncate_middle(
+            &line.name,
+            name_width.max(MIN_NAME_WIDTH),
+            Charset::current().ellipsis(),
+        ),
+        None => line.name.clone(),
+    };
+    text.push_str(&paint(line.name_style, &name));
+
+    if line.has_columns() {
+        text.push_str(
+            &" ".repeat(
+                widths
+                    .name_end
+                    .saturating_sub(prefix_width + display_width(&name)),
+            ),
         );
+
+        if widths.count > 0 {
+            text.push(' ');
+            let count_style = options
+                .colors_for(line.path)
+                .and_then(|theme| theme.count.as_deref());
+            text.push_str(&paint(count_style, &pad(&line.count, widths.count, true)));
+        }
+        if widths.size > 0 {
+            text.push(' ');
+            text.push_str(&paint(line.size_style, &pad(&line.size, widths.size, true)));
+        }
+        if widths.bar > 0 {
+            text.push(' ');
+            text.push_str(&paint(line.size_style, &pad(&line.bar, widths.bar, true)));
+        }
+    }
+
+    if !line.details.is_empty() {
+        text.push(' ');
+        text.push_str(&line.details);
     }
+
+    println!(
+        "{}{}",
+        options.branch(&line.branch),
+        options.highlight(line.path, text)
+    );
+}
+
+/// Summary of the entries left out by `max_entries`, like "… 1,024 more files (12.00 MB)"
+fn overflow_summary(files: &[&FileObject], directories: &[&DirectoryObject]) -> String {
+    let mut counts = Vec::new();
+    if !files.is_empty() {
+        counts.push(format!(
+            "{} more {}",
+            format_count(files.len() as u64),
+            if files.len() == 1 { "file" } else { "files" }
+        ));
+    }
+    if !directories.is_empty() {
+        counts.push(format!(
+            "{} more {}",
+            format_count(directories.len() as u64),
+            if directories.len() == 1 {
+                "directory"
+            } else {
+                "directories"
+            }
+        ));
+    }
+
+    let size = files.iter().map(|file| file.size).sum::<u64>()
+        + directories
+            .iter()
+            .map(|directory| directory.total_size())
+            .sum::<u64>();
+
+    format!(
+        "{} {} ({})",
+        Charset::current().ellipsis(),
+        counts.join(" and "),
+        bytes_to_best_size(size)
+    )
 }
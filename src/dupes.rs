@@ -0,0 +1,288 @@
+use crate::json::JsonValue;
+use crate::scan::{DirectoryObject, FileObject};
+use crate::size::bytes_to_best_size;
+use crate::styles::{Color, TextStyle};
+use crate::utils::relative_path;
+use std::collections::hash_map::DefaultHasher;
+use std::collections::HashMap;
+use std::fs::File;
+use std::hash::Hasher;
+use std::io::{self, Read};
+
+/// Number of bytes read from the start of a file for the first round of hashing
+const PARTIAL_HASH_LENGTH: u64 = 4096;
+
+pub struct DuplicateGroup {
+    pub size: u64,
+    /// Paths of each copy, a copy with hard links has more than one path
+    pub copies: Vec<Vec<String>>,
+}
+
+impl DuplicateGroup {
+    /// Bytes that could be freed by keeping a single copy
+    pub fn wasted_bytes(&self) -> u64 {
+        self.size * (self.copies.len() as u64 - 1)
+    }
+
+    pub fn paths(&self) -> impl Iterator<Item = &String> {
+        self.copies.iter().flatten()
+    }
+}
+
+/// Find files with identical content, files are grouped by size first, then by a hash of
+/// their first few kilobytes and finally compared byte by byte. Hard links to the same file
+/// count as a single copy
+pub fn find_duplicates(tree: &DirectoryObject) -> Vec<DuplicateGroup> {
+    let mut files_by_size: HashMap<u64, Vec<&FileObject>> = HashMap::new();
+    collect_files_by_size(tree, &mut files_by_size);
+
+    let mut groups = Vec::new();
+
+    for (size, files) in files_by_size {
+        let copies = group_hard_links(&files);
+        if copies.len() < 2 {
+            continue;
+        }
+
+        for partial_group in group_by_partial_hash(&copies) {
+            for full_group in group_by_content(&partial_group) {
+                let mut copies: Vec<Vec<String>> = full_group
+                    .iter()
+                    .map(|links| {
+                        let mut paths: Vec<String> =
+                            links.iter().map(|file| file.path.clone()).collect();
+                        paths.sort();
+                        paths
+                    })
+                    .collect();
+                copies.sort();
+                groups.push(DuplicateGroup { size, copies });
+            }
+        }
+    }
+
+    groups.sort_by(|a, b| {
+        b.wasted_bytes()
+            .cmp(&a.wasted_bytes())
+            .then(a.copies.cmp(&b.copies))
+    });
+    groups
+}
+
+fn collect_files_by_size<'a>(
+    directory: &'a DirectoryObject,
+    files_by_size: &mut HashMap<u64, Vec<&'a FileObject>>,
+) {
+    for file in &directory.files {
+        // Empty files are all identical, reporting them would only be noise
+        if file.size > 0 {
+            files_by_size.entry(file.size).or_default().push(file);
+        }
+    }
+
+    for sub_directory in &directory.directories {
+        collect_files_by_size(sub_directory, files_by_size);
+    }
+}
+
+/// Group paths that are hard links to the same file, each group is read as a single copy
+fn group_hard_links<'a>(files: &[&'a FileObject]) -> Vec<Vec<&'a FileObject>> {
+    let mut copies: Vec<Vec<&FileObject>> = Vec::new();
+    let mut copy_by_id: HashMap<(u64, u64), usize> = HashMap::new();
+
+    for file in files {
+        let file_id = file.metadata.as_ref().and_then(|metadata| metadata.file_id);
+        match file_id.and_then(|file_id| copy_by_id.get(&file_id)) {
+            Some(&index) => copies[index].push(file),
+            None => {
+                if let Some(file_id) = file_id {
+                    copy_by_id.insert(file_id, copies.len());
+                }
+                copies.push(vec![file]);
+            }
+        }
+    }
+
+    copies
+}
+
+/// Split copies into groups with the same hash of their first few kilobytes, groups with a
+/// single copy are dropped
+fn group_by_partial_hash<'a>(copies: &[Vec<&'a FileObject>]) -> Vec<Vec<Vec<&'a FileObject>>> {
+    let mut groups: HashMap<u64, Vec<Vec<&FileObject>>> = HashMap::new();
+
+    for links in copies {
+        if let Some(hash) = hash_start_of_file(&links[0].path) {
+            groups.entry(hash).or_default().push(links.clone());
+        }
+    }
+
+    groups
+        .into_values()
+        .filter(|group| group.len() > 1)
+        .collect()
+}
+
+/// Split copies with the same partial hash into groups with the same bytes, which also keeps a
+/// hash collision from being reported as a duplicate. Groups with a single copy are dropped
+fn group_by_content<'a>(copies: &[Vec<&'a FileObject>]) -> Vec<Vec<Vec<&'a FileObject>>> {
+    let mut groups: Vec<Vec<Vec<&FileObject>>> = Vec::new();
+
+    for links in copies {
+        let group = groups
+            .iter_mut()
+            .find(|group| same_content(&group[0][0].path, &links[0].path).unwrap_or(false));
+        match group {
+            Some(group) => group.push(links.clone()),
+            None => groups.push(vec![links.clone()]),
+        }
+    }
+
+    groups.retain(|group| group.len() > 1);
+    groups
+}
+
+fn hash_start_of_file(path: &str) -> Option<u64> {
+    let mut reader = File::open(path).ok()?.take(PARTIAL_HASH_LENGTH);
+
+    let mut hasher = DefaultHasher::new();
+    let mut buffer = [0u8; 64 * 1024];
+    loop {
+        let length = reader.read(&mut buffer).ok()?;
+        if length == 0 {
+            break;
+        }
+        hasher.write(&buffer[..length]);
+    }
+
+    Some(hasher.finish())
+}
+
+fn same_content(path: &str, other_path: &str) -> io::Result<bool> {
+    let mut file = File::open(path)?;
+    let mut other_file = File::open(other_path)?;
+    let mut buffer = vec![0u8; 64 * 1024];
+    let mut other_buffer = vec![0u8; 64 * 1024];
+
+    loop {
+        let length = read_full(&mut file, &mut buffer)?;
+        let other_length = read_full(&mut other_file, &mut other_buffer)?;
+        if buffer[..length] != other_buffer[..other_length] {
+            return Ok(false);
+        }
+        if length == 0 {
+            return Ok(true);
+        }
+    }
+}
+
+/// Read until the buffer is full or the file ends, returning the number of bytes read
+fn read_full(file: &mut File, buffer: &mut [u8]) -> io::Result<usize> {
+    let mut filled = 0;
+    while filled < buffer.len() {
+        match file.read(&mut buffer[filled..])? {
+            0 => break,
+            length => filled += length,
+        }
+    }
+    Ok(filled)
+}
+
+pub fn total_wasted_bytes(groups: &[DuplicateGroup]) -> u64 {
+    groups.iter().map(|group| group.wasted_bytes()).sum()
+}
+
+pub fn display_duplicates(groups: &[DuplicateGroup], root: &DirectoryObject) {
+    for group in groups {
+        println!(
+            "{}{} copies of {}, {} wasted{}",
+            TextStyle::Bold.to_ansi_style_string(),
+            group.copies.len(),
+            bytes_to_best_size(group.size),
+            bytes_to_best_size(group.wasted_bytes()),
+            TextStyle::Reset.to_ansi_style_string()
+        );
+        for links in &group.copies {
+            let paths: Vec<String> = links
+                .iter()
+                .map(|path| relative_path(&root.path, path))
+                .collect();
+            match paths.split_first() {
+                Some((path, [])) => println!("    {}", path),
+                Some((path, links)) => {
+                    println!("    {} (hard linked as {})", path, links.join(", "))
+                }
+                None => {}
+            }
+        }
+        println!();
+    }
+
+    println!(
+        "{} duplicate groups, {} files, {} wasted",
+        groups.len(),
+        groups.iter().map(|group| group.copies.len()).sum::<usize>(),
+        bytes_to_best_size(total_wasted_bytes(groups))
+    );
+}
+
+/// Labels for every duplicate file, used to mark them in the tree view
+pub fn duplicate_annotations(groups: &[DuplicateGroup]) -> HashMap<String, String> {
+    let mut annotations = HashMap::new();
+
+    for (i, group) in groups.iter().enumerate() {
+        for path in group.paths() {
+            annotations.insert(
+                path.clone(),
+                format!(
+                    "{}[duplicate #{}]{}",
+                    Color::Yellow.to_ansi_color_string(),
+                    i + 1,
+                    Color::Reset.to_ansi_color_string()
+                ),
+            );
+        }
+    }
+
+    annotations
+}
+
+pub fn duplicates_to_json(groups: &[DuplicateGroup], root: &DirectoryObject) -> JsonValue {
+    JsonValue::object()
+        .with("wasted_bytes", total_wasted_bytes(groups).into())
+        .with(
+            "groups",
+            groups
+                .iter()
+                .map(|group| {
+                    JsonValue::object()
+                        .with("size", group.size.into())
+                        .with("wasted_bytes", group.wasted_bytes().into())
+                        .with(
+                            "paths",
+                            group
+                                .paths()
+                                .map(|path| relative_path(&root.path, path).into())
+                                .collect::<Vec<JsonValue>>()
+                                .into(),
+                        )
+                        .with(
+                            "copies",
+                            group
+                                .copies
+                                .iter()
+                                .map(|links| {
+                                    links
+                                        .iter()
+                                        .map(|path| relative_path(&root.path, path).into())
+                                        .collect::<Vec<JsonValue>>()
+                                        .into()
+                                })
+                                .collect::<Vec<JsonValue>>()
+                                .into(),
+                        )
+                })
+                .collect::<Vec<JsonValue>>()
+                .into(),
+        )
+}
@@ -0,0 +1,138 @@
+use crate::glob::glob_match;
+use crate::scan::{DirectoryObject, FileObject};
+use std::time::SystemTime;
+
+/// Range of modification times a file has to fall in to be kept
+pub struct ModifiedWindow {
+    pub newer_than: Option<SystemTime>,
+    pub older_than: Option<SystemTime>,
+}
+
+impl ModifiedWindow {
+    pub fn is_empty(&self) -> bool {
+        self.newer_than.is_none() && self.older_than.is_none()
+    }
+
+    pub fn contains(&self, file: &FileObject) -> bool {
+        let modified = match file
+            .metadata
+            .as_ref()
+            .and_then(|metadata| metadata.modified)
+        {
+            Some(modified) => modified,
+            None => return self.is_empty(),
+        };
+
+        self.newer_than
+            .is_none_or(|newer_than| modified >= newer_than)
+            && self
+                .older_than
+                .is_none_or(|older_than| modified < older_than)
+    }
+}
+
+/// Range of sizes in bytes a file has to fall in to be kept, both ends are included
+pub struct SizeRange {
+    pub min: Option<u64>,
+    pub max: Option<u64>,
+}
+
+impl SizeRange {
+    pub fn is_empty(&self) -> bool {
+        self.min.is_none() && self.max.is_none()
+    }
+
+    pub fn contains(&self, size: u64) -> bool {
+        self.min.is_none_or(|min| size >= min) && self.max.is_none_or(|max| size <= max)
+    }
+}
+
+/// Remove every file that does not match `keep_file` and every directory that does not match
+/// `keep_directory`, along with the directories left empty, the root directory is always
+/// kept. Returns whether the directory still has any files
+pub fn retain_entries(
+    directory: &mut DirectoryObject,
+    keep_file: &impl Fn(&FileObject) -> bool,
+    keep_directory: &impl Fn(&DirectoryObject) -> bool,
+) -> bool {
+    directory.files.retain(|file| keep_file(file));
+    directory.directories.retain_mut(|sub_directory| {
+        keep_directory(sub_directory) && retain_entries(sub_directory, keep_file, keep_directory)
+    });
+    directory.update_totals();
+
+    !directory.files.is_empty() || !directory.directories.is_empty()
+}
+
+/// Glob patterns given on the command line for a single scan, patterns containing a `/` are
+/// matched against the path relative to the scanned directory, others against the name
+pub struct PathPatterns {
+    pub include: Vec<String>,
+    pub exclude: Vec<String>,
+}
+
+impl PathPatterns {
+    pub fn is_empty(&self) -> bool {
+        self.include.is_empty() && self.exclude.is_empty()
+    }
+
+    /// Whether a file should be kept, given its path relative to the scanned directory
+    pub fn matches(&self, relative_path: &str) -> bool {
+        if self.excludes(relative_path) {
+            return false;
+        }
+
+        let name = relative_path.rsplit('/').next().unwrap_or(relative_path);
+        self.include.is_empty()
+            || self.include.iter().any(|pattern| {
+                if pattern.contains('/') {
+                    glob_match(pattern, relative_path)
+                } else {
+                    glob_match(pattern, name)
+                }
+            })
+    }
+
+    /// Whether a file or directory is hidden by an exclude pattern, given its path relative to
+    /// the scanned directory. Excluding a directory excludes everything inside it
+    pub fn excludes(&self, relative_path: &str) -> bool {
+        let components: Vec<&str> = relative_path.split('/').collect();
+
+        self.exclude.iter().any(|pattern| {
+            (1..=components.len()).any(|length| {
+                if pattern.contains('/') {
+                    glob_match(pattern, &components[..length].join("/"))
+                } else {
+                    glob_match(pattern, components[length - 1])
+                }
+            })
+        })
+    }
+}
+
+/// Remove directories that have no files in them or in any of their subdirectories, the
+/// root directory is always kept. Returns whether the directory has any files
+pub fn prune_empty_directories(directory: &mut DirectoryObject) -> bool {
+    directory.directories.retain_mut(prune_empty_directories);
+
+    !directory.files.is_empty() || !directory.directories.is_empty()
+}
+
+/// Merge every directory that holds nothing but a single subdirectory with that subdirectory,
+/// so a chain like src/main/java is shown as one entry
+pub fn compact_directories(directory: &mut DirectoryObject) {
+    for sub_directory in directory.directories.iter_mut() {
+        let mut name = sub_directory.base_name();
+
+        while sub_directory.files.is_empty() && sub_directory.directories.len() == 1 {
+            let child = sub_directory.directories.remove(0);
+            name = format!("{}/{}", name, child.base_name());
+            *sub_directory = DirectoryObject {
+                name: name.clone(),
+                ..child
+            };
+        }
+
+        compact_directories(sub_directory);
+    }
+}
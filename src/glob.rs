@@ -0,0 +1,213 @@
+/// Match text against a shell style glob pattern. `*` matches anything except `/`, `**`
+/// matches anything including `/`, `?` matches a single character and `[abc]`, `[a-z]` or
+/// `[!abc]` match a single character from a set, neither of them matching `/`. A `**/` at
+/// the start of a path component also matches no directories at all
+pub fn glob_match(pattern: &str, text: &str) -> bool {
+    let pattern: Vec<char> = pattern.chars().collect();
+    let text: Vec<char> = text.chars().collect();
+
+    match_from(&pattern, &text)
+}
+
+/// Match with the iterative two pointer algorithm. On a mismatch the last `*` takes one
+/// more character, and once it would have to cross a `/` the last `**/` takes one more
+/// directory or the last `**` one more character, so patterns with many stars take
+/// polynomial rather than exponential time
+fn match_from(pattern: &[char], text: &[char]) -> bool {
+    let mut p = 0;
+    let mut t = 0;
+    // Where to resume the pattern and the text after the last `*`, `**/` and `**`
+    let mut star: Option<(usize, usize)> = None;
+    let mut directories: Option<(usize, usize)> = None;
+    let mut globstar: Option<(usize, usize)> = None;
+
+    while p < pattern.len() || t < text.len() {
+        if p < pattern.len() {
+            match pattern[p] {
+                // A "**/" starting a path component also matches no directories at all
+                '*' if pattern.get(p + 1) == Some(&'*')
+                    && pattern.get(p + 2) == Some(&'/')
+                    && (p == 0 || pattern[p - 1] == '/') =>
+                {
+                    p += 3;
+                    directories = Some((p, t));
+                    continue;
+                }
+                '*' if pattern.get(p + 1) == Some(&'*') => {
+                    while pattern.get(p) == Some(&'*') {
+                        p += 1;
+                    }
+                    // A `**` can take anything the stars before it could
+                    globstar = Some((p, t));
+                    directories = None;
+                    star = None;
+                    continue;
+                }
+                '*' => {
+                    p += 1;
+                    star = Some((p, t));
+                    continue;
+                }
+                '?' if t < text.len() && text[t] != '/' => {
+                    p += 1;
+                    t += 1;
+                    continue;
+                }
+                '?' => {}
+                '[' => match match_class(&pattern[p + 1..], text.get(t)) {
+                    Some((true, length)) if t < text.len() && text[t] != '/' => {
+                        p += 1 + length;
+                        t += 1;
+                        continue;
+                    }
+                    Some(_) => {}
+                    // An unclosed bracket is matched literally
+                    None if text.get(t) == Some(&'[') => {
+                        p += 1;
+                        t += 1;
+                        continue;
+                    }
+                    None => {}
+                },
+                c if text.get(t) == Some(&c) => {
+                    p += 1;
+                    t += 1;
+                    continue;
+                }
+                _ => {}
+            }
+        }
+
+        // Mismatch, let the last `*` take one more character of the current directory
+        if let Some((star_p, star_t)) = star {
+            if star_t < text.len() && text[star_t] != '/' {
+                star = Some((star_p, star_t + 1));
+                p = star_p;
+                t = star_t + 1;
+                continue;
+            }
+        }
+
+        // Then the last `**/` one more directory
+        if let Some((directories_p, directories_t)) = directories {
+            if let Some(position) = text[directories_t..].iter().position(|&c| c == '/') {
+                directories = Some((directories_p, directories_t + position + 1));
+                star = None;
+                p = directories_p;
+                t = directories_t + position + 1;
+                continue;
+            }
+        }
+
+        // Then the last `**` one more character of any kind
+        if let Some((globstar_p, globstar_t)) = globstar {
+            if globstar_t < text.len() {
+                globstar = Some((globstar_p, globstar_t + 1));
+                directories = None;
+                star = None;
+                p = globstar_p;
+                t = globstar_t + 1;
+                continue;
+            }
+        }
+
+        return false;
+    }
+
+    true
+}
+
+/// Match a character against the class starting after a `[`, returning whether it matched
+/// and the length of the class including the closing `]`
+fn match_class(class: &[char], c: Option<&char>) -> Option<(bool, usize)> {
+    let negated = matches!(class.first(), Some('!') | Some('^'));
+    let mut index = if negated { 1 } else { 0 };
+    let mut matched = false;
+    let mut first = true;
+
+    loop {
+        let current = *class.get(index)?;
+        if current == ']' && !first {
+            break;
+        }
+        first = false;
+
+        if class.get(index + 1) == Some(&'-') && class.get(index + 2).is_some_and(|&end| end != ']')
+        {
+            let end = class[index + 2];
+            if c.is_some_and(|&c| current <= c && c <= end) {
+                matched = true;
+            }
+            index += 3;
+        } else {
+            if c == Some(&current) {
+                matched = true;
+            }
+            index += 1;
+        }
+    }
+
+    Some((matched != negated, index + 1))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::glob_match;
+
+    #[test]
+    fn matches_literals_and_wildcards() {
+        assert!(glob_match("main.rs", "main.rs"));
+        assert!(!glob_match("main.rs", "main.rs.bak"));
+        assert!(glob_match("*.rs", "main.rs"));
+        assert!(glob_match("*", ""));
+        assert!(glob_match("ma?n.rs", "main.rs"));
+        assert!(!glob_match("ma?n.rs", "man.rs"));
+        assert!(glob_match("*a*b*c", "xxaxxbxxc"));
+        assert!(!glob_match("*a*b*c", "xxaxxcxxb"));
+    }
+
+    #[test]
+    fn single_star_stays_in_one_directory() {
+        assert!(glob_match("src/*.rs", "src/main.rs"));
+        assert!(!glob_match("src/*.rs", "src/system/config.rs"));
+        assert!(!glob_match("*", "a/b"));
+        assert!(!glob_match("a?b", "a/b"));
+    }
+
+    #[test]
+    fn double_star_crosses_directories() {
+        assert!(glob_match("src/**/*.rs", "src/system/config.rs"));
+        assert!(glob_match("src/**/*.rs", "src/main.rs"));
+        assert!(glob_match("**/*.rs", "main.rs"));
+        assert!(glob_match("**", "a/b/c"));
+        assert!(glob_match("a/**", "a/b/c"));
+        assert!(glob_match("a**z", "a/b/z"));
+        assert!(!glob_match("src/**/*.rs", "test/main.rs"));
+        assert!(!glob_match("**/*.rs", "main.txt"));
+    }
+
+    #[test]
+    fn matches_classes() {
+        assert!(glob_match("[abc].txt", "b.txt"));
+        assert!(!glob_match("[abc].txt", "d.txt"));
+        assert!(glob_match("[a-z]1", "q1"));
+        assert!(glob_match("[!a-z]1", "Q1"));
+        assert!(!glob_match("[!a-z]1", "q1"));
+        assert!(glob_match("[]]", "]"));
+        assert!(!glob_match("a[/]b", "a/b"));
+        assert!(!glob_match("a[!x]b", "a/b"));
+    }
+
+    #[test]
+    fn matches_unclosed_bracket_literally() {
+        assert!(glob_match("[abc", "[abc"));
+        assert!(!glob_match("[abc", "a"));
+    }
+
+    #[test]
+    fn handles_many_stars_quickly() {
+        let text = "a".repeat(200);
+        assert!(!glob_match(&format!("{}b", "*a".repeat(50)), &text));
+        assert!(!glob_match(&format!("{}b", "**a".repeat(50)), &text));
+    }
+}
@@ -0,0 +1,143 @@
+use crate::loc::read_text_file;
+use crate::regex::Regex;
+use crate::scan::DirectoryObject;
+use crate::styles::{Color, TextStyle};
+use std::collections::HashMap;
+
+/// Longest part of a matching line shown next to a file
+const MAX_LINE_LENGTH: usize = 80;
+
+pub struct FileMatches {
+    pub count: usize,
+    pub first_line_number: usize,
+    pub first_line: String,
+    /// Byte range of the first match within `first_line`
+    pub first_match: (usize, usize),
+}
+
+/// Search the contents of every text file in the tree, keyed by the path of the file
+pub fn search_tree(tree: &DirectoryObject, regex: &Regex) -> HashMap<String, FileMatches> {
+    let mut matches = HashMap::new();
+    search_directory(tree, regex, &mut matches);
+    matches
+}
+
+fn search_directory(
+    directory: &DirectoryObject,
+    regex: &Regex,
+    matches: &mut HashMap<String, FileMatches>,
+) {
+    for file in &directory.files {
+        if let Some(file_matches) = search_file(&file.path, regex) {
+            matches.insert(file.path.clone(), file_matches);
+        }
+    }
+
+    for sub_directory in &directory.directories {
+        search_directory(sub_directory, regex, matches);
+    }
+}
+
+fn search_file(path: &str, regex: &Regex) -> Option<FileMatches> {
+    let content = read_text_file(path)?;
+    let mut file_matches: Option<FileMatches> = None;
+
+    for (i, line) in content.lines().enumerate() {
+        let line_matches = regex.find_all(line);
+        if line_matches.is_empty() {
+            continue;
+        }
+
+        match file_matches.as_mut() {
+            Some(file_matches) => file_matches.count += line_matches.len(),
+            None => {
+                file_matches = Some(FileMatches {
+                    count: line_matches.len(),
+                    first_line_number: i + 1,
+                    first_line: line.to_string(),
+                    first_match: line_matches[0],
+                })
+            }
+        }
+    }
+
+    file_matches
+}
+
+pub fn total_matches(matches: &HashMap<String, FileMatches>) -> usize {
+    matches
+        .values()
+        .map(|file_matches| file_matches.count)
+        .sum()
+}
+
+/// Labels with the match count of each file and optionally its first matching line
+pub fn match_annotations(
+    matches: &HashMap<String, FileMatches>,
+    show_first_line: bool,
+) -> HashMap<String, String> {
+    matches
+        .iter()
+        .map(|(path, file_matches)| {
+            let mut annotation = format!(
+                "{}({} {}){}",
+                Color::Yellow.to_ansi_color_string(),
+                file_matches.count,
+                if file_matches.count == 1 {
+                    "match"
+                } else {
+                    "matches"
+                },
+                Color::Reset.to_ansi_color_string()
+            );
+
+            if show_first_line {
+                annotation.push_str(&format!(
+                    " {}{}:{} {}",
+                    Color::Green.to_ansi_color_string(),
+                    file_matches.first_line_number,
+                    Color::Reset.to_ansi_color_string(),
+                    highlight_first_match(file_matches)
+                ));
+            }
+
+            (path.clone(), annotation)
+        })
+        .collect()
+}
+
+/// The first matching line with the match in bold red, trimmed to fit next to the tree
+fn highlight_first_match(file_matches: &FileMatches) -> String {
+    let line = &file_matches.first_line;
+    let (match_start, match_end) = file_matches.first_match;
+
+    let before = line[..match_start].trim_start();
+    let matched = &line[match_start..match_end];
+    let after = line[match_end..].trim_end();
+
+    // Keep the match visible by cutting the start of long lines first
+    let mut before_chars: Vec<char> = before.chars().collect();
+    let matched_length = matched.chars().count();
+    let mut cut_start = false;
+    while before_chars.len() + matched_length > MAX_LINE_LENGTH / 2 && !before_chars.is_empty() {
+        before_chars.remove(0);
+        cut_start = true;
+    }
+    let remaining = MAX_LINE_LENGTH.saturating_sub(before_chars.len() + matched_length);
+    let cut_end = after.chars().count() > remaining;
+
+    format!(
+        "{}{}{}{}{}{}{}",
+        if cut_start { "..." } else { "" },
+        before_chars.into_iter().collect::<String>(),
+        TextStyle::Bold.to_ansi_style_string(),
+        Color::Red.to_ansi_color_string(),
+        matched,
+        Color::Reset.to_ansi_color_string(),
+        if cut_end {
+            format!("{}...", after.chars().take(remaining).collect::<String>())
+        } else {
+            after.to_string()
+        }
+    )
+}
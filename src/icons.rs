@@ -0,0 +1,243 @@
+use crate::colors::ExtensionGroup;
+use crate::metadata::EntryMetadata;
+use crate::scan::{DirectoryObject, FileObject};
+use crate::system::config::{get_optional_setting_from_config, ConfigOption};
+use crate::OS;
+use std::collections::HashMap;
+
+pub const DEFAULT_ICON_SET: &str = "nerd";
+
+/// Icons of well known file names as (names, Nerd Font icon, Unicode icon)
+const NAME_ICONS: &[(&[&str], &str, &str)] = &[
+    (&["Cargo.toml", "Cargo.lock"], "\u{e7a8}", "λ"),
+    (
+        &[
+            "Dockerfile",
+            "docker-compose.yml",
+            "docker-compose.yaml",
+            ".dockerignore",
+        ],
+        "\u{f308}",
+        "⚓",
+    ),
+    (
+        &["package.json", "package-lock.json", ".npmrc"],
+        "\u{e71e}",
+        "⬢",
+    ),
+    (
+        &[".gitignore", ".gitattributes", ".gitmodules"],
+        "\u{e702}",
+        "±",
+    ),
+    (&["Makefile", "CMakeLists.txt"], "\u{f489}", "⚒"),
+    (&["LICENSE", "LICENSE.md", "COPYING"], "\u{f0fc}", "§"),
+];
+
+/// Icons of extensions as (extensions, Nerd Font icon, Unicode icon)
+const EXTENSION_ICONS: &[(&[&str], &str, &str)] = &[
+    (&["rs"], "\u{e7a8}", "λ"),
+    (&["py", "pyi"], "\u{e73c}", "λ"),
+    (&["js", "mjs", "cjs", "jsx"], "\u{e74e}", "λ"),
+    (&["ts", "tsx"], "\u{e628}", "λ"),
+    (&["go"], "\u{e626}", "λ"),
+    (&["c", "h"], "\u{e61e}", "λ"),
+    (&["cpp", "cc", "cxx", "hpp"], "\u{e61d}", "λ"),
+    (&["java", "kt"], "\u{e738}", "λ"),
+    (&["rb"], "\u{e739}", "λ"),
+    (&["swift"], "\u{e755}", "λ"),
+    (&["html", "htm"], "\u{e736}", "◇"),
+    (&["css", "scss", "sass"], "\u{e749}", "◇"),
+    (
+        &["sh", "bash", "zsh", "fish", "ps1", "bat"],
+        "\u{f489}",
+        "$",
+    ),
+    (&["md", "markdown"], "\u{e73e}", "¶"),
+    (&["txt", "log", "rst"], "\u{f15c}", "¶"),
+    (&["pdf"], "\u{f1c1}", "¶"),
+    (
+        &["json", "jsonl", "csv", "xml", "sql", "db", "sqlite"],
+        "\u{e60b}",
+        "≡",
+    ),
+    (
+        &["toml", "yml", "yaml", "ini", "cfg", "conf", "env"],
+        "\u{e615}",
+        "⚙",
+    ),
+    (&["lock"], "\u{f023}", "⊘"),
+];
+
+/// Icons of the extension groups of the dircolors palette
+const GROUP_ICONS: &[(ExtensionGroup, &str, &str)] = &[
+    (ExtensionGroup::Archive, "\u{f1c6}", "▣"),
+    (ExtensionGroup::ImageAndVideo, "\u{f1c5}", "▨"),
+    (ExtensionGroup::Audio, "\u{f1c7}", "♫"),
+];
+
+/// Icons of kinds of entries as (kind, Nerd Font icon, Unicode icon)
+const KIND_ICONS: &[(&str, &str, &str)] = &[
+    ("directory", "\u{f07b}", "▸"),
+    ("symlink", "\u{f0c1}", "↪"),
+    ("executable", "\u{f489}", "▶"),
+    ("file", "\u{f15b}", "·"),
+];
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum IconSet {
+    /// Icons from the private use area of Nerd Fonts
+    NerdFont,
+    /// Symbols that any font can show, for terminals without a Nerd Font
+    Unicode,
+}
+
+impl IconSet {
+    pub fn from_string(string: &str) -> Option<IconSet> {
+        match string {
+            "nerd" => Some(IconSet::NerdFont),
+            "unicode" => Some(IconSet::Unicode),
+            _ => None,
+        }
+    }
+
+    fn pick<'a>(&self, nerd_font: &'a str, unicode: &'a str) -> &'a str {
+        match self {
+            IconSet::NerdFont => nerd_font,
+            IconSet::Unicode => unicode,
+        }
+    }
+}
+
+pub struct Icons {
+    set: IconSet,
+    /// Icons from the config, keyed by a file name, an extension like .rs or a kind of entry
+    overrides: HashMap<String, String>,
+}
+
+impl Icons {
+    /// Load the icon set passed with --icon-set or set in the config, with the icons
+    /// setting of the config applied on top
+    pub fn load(set: Option<String>, os: &OS) -> Result<Icons, String> {
+        let set = set
+            .or_else(|| {
+                get_optional_setting_from_config(ConfigOption::IconSet, os)
+                    .map(|setting| setting.value)
+                    .filter(|value| !value.is_empty() && value != "None")
+            })
+            .unwrap_or(DEFAULT_ICON_SET.to_string());
+
+        let set = IconSet::from_string(&set).ok_or("icon set must be \"nerd\" or \"unicode\"")?;
+
+        let mut icons = Icons {
+            set,
+            overrides: HashMap::new(),
+        };
+
+        if let Some(setting) = get_optional_setting_from_config(ConfigOption::Icons, os) {
+            icons
+                .set_overrides(&setting.value)
+                .map_err(|e| format!("invalid icons setting: {}", e))?;
+        }
+
+        Ok(icons)
+    }
+
+    /// Apply a comma separated list of key=icon pairs
+    pub fn set_overrides(&mut self, list: &str) -> Result<(), String> {
+        for pair in list.split(',').map(|pair| pair.trim()) {
+            if pair.is_empty() || pair == "None" {
+                continue;
+            }
+
+            match pair.split_once('=') {
+                Some((key, icon)) => {
+                    let key = key.trim();
+                    self.overrides.insert(
+                        key.strip_prefix('*').unwrap_or(key).to_string(),
+                        icon.trim().to_string(),
+                    );
+                }
+                None => return Err(format!("expected key=icon, found \"{}\"", pair)),
+            }
+        }
+
+        Ok(())
+    }
+
+    fn kind_icon(&self, kind: &str) -> &str {
+        if let Some(icon) = self.overrides.get(kind) {
+            return icon;
+        }
+
+        KIND_ICONS
+            .iter()
+            .find(|(name, _, _)| *name == kind)
+            .map_or("", |(_, nerd_font, unicode)| {
+                self.set.pick(nerd_font, unicode)
+            })
+    }
+
+    pub fn directory_icon(&self, directory: &DirectoryObject) -> &str {
+        if is_symlink(directory.metadata.as_ref()) {
+            return self.kind_icon("symlink");
+        }
+
+        self.kind_icon("directory")
+    }
+
+    /// Icon of a file, from its name, then its extension, then its kind
+    pub fn file_icon(&self, file: &FileObject) -> &str {
+        if is_symlink(file.metadata.as_ref()) {
+            return self.kind_icon("symlink");
+        }
+
+        let ending = file.ending.to_lowercase();
+        if let Some(icon) = self.overrides.get(&file.full_name).or_else(|| {
+            Some(&ending)
+                .filter(|ending| !ending.is_empty())
+                .and_then(|ending| self.overrides.get(&format!(".{}", ending)))
+        }) {
+            return icon;
+        }
+
+        let builtin_icon = NAME_ICONS
+            .iter()
+            .find(|(names, _, _)| names.contains(&file.full_name.as_str()))
+            .or_else(|| {
+                EXTENSION_ICONS
+                    .iter()
+                    .find(|(extensions, _, _)| extensions.contains(&ending.as_str()))
+            })
+            .map(|(_, nerd_font, unicode)| self.set.pick(nerd_font, unicode))
+            .or_else(|| {
+                GROUP_ICONS
+                    .iter()
+                    .find(|(group, _, _)| group.extensions().contains(&ending.as_str()))
+                    .map(|(_, nerd_font, unicode)| self.set.pick(nerd_font, unicode))
+            });
+
+        match builtin_icon {
+            Some(icon) => icon,
+            None if is_executable(file.metadata.as_ref()) => self.kind_icon("executable"),
+            None => self.kind_icon("file"),
+        }
+    }
+}
+
+fn is_symlink(metadata: Option<&EntryMetadata>) -> bool {
+    match metadata {
+        Some(metadata) => {
+            metadata.mode.is_some_and(|mode| mode & 0o170000 == 0o120000)
+                // Reparse points are how Windows represents symbolic links
+                || metadata.attributes.is_some_and(|attributes| attributes & 0x400 != 0)
+        }
+        None => false,
+    }
+}
+
+fn is_executable(metadata: Option<&EntryMetadata>) -> bool {
+    metadata
+        .and_then(|metadata| metadata.mode)
+        .is_some_and(|mode| mode & 0o111 != 0)
+}
@@ -0,0 +1,539 @@
+use std::fmt::Write;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum JsonValue {
+    Null,
+    Bool(bool),
+    Number(f64),
+    String(String),
+    Array(Vec<JsonValue>),
+    Object(Vec<(String, JsonValue)>),
+}
+
+impl JsonValue {
+    pub fn object() -> JsonValue {
+        JsonValue::Object(Vec::new())
+    }
+
+    /// Add a key to an object, does nothing if the value is not an object
+    pub fn with(mut self, key: &str, value: JsonValue) -> JsonValue {
+        if let JsonValue::Object(entries) = &mut self {
+            entries.push((key.to_string(), value));
+        }
+        self
+    }
+
+    pub fn get(&self, key: &str) -> Option<&JsonValue> {
+        match self {
+            JsonValue::Object(entries) => entries.iter().find(|(k, _)| k == key).map(|(_, v)| v),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            JsonValue::String(value) => Some(value),
+            _ => None,
+        }
+    }
+
+    pub fn as_u64(&self) -> Option<u64> {
+        match self {
+            JsonValue::Number(value) if *value >= 0.0 => Some(*value as u64),
+            _ => None,
+        }
+    }
+
+    pub fn as_f64(&self) -> Option<f64> {
+        match self {
+            JsonValue::Number(value) => Some(*value),
+            _ => None,
+        }
+    }
+
+    pub fn as_array(&self) -> Option<&Vec<JsonValue>> {
+        match self {
+            JsonValue::Array(values) => Some(values),
+            _ => None,
+        }
+    }
+
+    /// Serialize the value with two space indentation
+    pub fn to_pretty_string(&self) -> String {
+        let mut output = String::new();
+        self.write_pretty(&mut output, 0);
+        output
+    }
+
+    /// Serialize the value without any whitespace
+    pub fn to_compact_string(&self) -> String {
+        let mut output = String::new();
+        self.write_compact(&mut output);
+        output
+    }
+
+    fn write_pretty(&self, output: &mut String, depth: usize) {
+        let indent = "  ".repeat(depth + 1);
+        match self {
+            JsonValue::Array(values) if !values.is_empty() => {
+                output.push_str("[\n");
+                for (i, value) in values.iter().enumerate() {
+                    output.push_str(&indent);
+                    value.write_pretty(output, depth + 1);
+                    if i < values.len() - 1 {
+                        output.push(',');
+                    }
+                    output.push('\n');
+                }
+                output.push_str(&"  ".repeat(depth));
+                output.push(']');
+            }
+            JsonValue::Object(entries) if !entries.is_empty() => {
+                output.push_str("{\n");
+                for (i, (key, value)) in entries.iter().enumerate() {
+                    output.push_str(&indent);
+                    write_string(output, key);
+                    output.push_str(": ");
+                    value.write_pretty(output, depth + 1);
+                    if i < entries.len() - 1 {
+                        output.push(',');
+                    }
+                    output.push('\n');
+                }
+                output.push_str(&"  ".repeat(depth));
+                output.push('}');
+            }
+            _ => self.write_compact(output),
+        }
+    }
+
+    fn write_compact(&self, output: &mut String) {
+        match self {
+            JsonValue::Null => output.push_str("null"),
+            JsonValue::Bool(value) => output.push_str(if *value { "true" } else { "false" }),
+            JsonValue::Number(value) => {
+                if value.fract() == 0.0 && value.abs() < 1e15 {
+                    let _ = write!(output, "{}", *value as i64);
+                } else if value.is_finite() {
+                    let _ = write!(output, "{}", value);
+                } else {
+                    output.push_str("null");
+                }
+            }
+            JsonValue::String(value) => write_string(output, value),
+            JsonValue::Array(values) => {
+                output.push('[');
+                for (i, value) in values.iter().enumerate() {
+                    if i > 0 {
+                        output.push(',');
+                    }
+                    value.write_compact(output);
+                }
+                output.push(']');
+            }
+            JsonValue::Object(entries) => {
+                output.push('{');
+                for (i, (key, value)) in entries.iter().enumerate() {
+                    if i > 0 {
+                        output.push(',');
+                    }
+                    write_string(output, key);
+                    output.push(':');
+                    value.write_compact(output);
+                }
+                output.push('}');
+            }
+        }
+    }
+
+    pub fn parse(input: &str) -> Result<JsonValue, String> {
+        let mut parser = Parser {
+            chars: input.chars().collect(),
+            position: 0,
+            depth: 0,
+        };
+        let value = parser.parse_value()?;
+        parser.skip_whitespace();
+        if parser.position < parser.chars.len() {
+            return Err(format!(
+                "unexpected trailing characters at position {}",
+                parser.position
+            ));
+        }
+        Ok(value)
+    }
+}
+
+impl From<&str> for JsonValue {
+    fn from(value: &str) -> Self {
+        JsonValue::String(value.to_string())
+    }
+}
+
+impl From<String> for JsonValue {
+    fn from(value: String) -> Self {
+        JsonValue::String(value)
+    }
+}
+
+impl From<u64> for JsonValue {
+    fn from(value: u64) -> Self {
+        JsonValue::Number(value as f64)
+    }
+}
+
+impl From<i64> for JsonValue {
+    fn from(value: i64) -> Self {
+        JsonValue::Number(value as f64)
+    }
+}
+
+impl From<f64> for JsonValue {
+    fn from(value: f64) -> Self {
+        JsonValue::Number(value)
+    }
+}
+
+impl From<bool> for JsonValue {
+    fn from(value: bool) -> Self {
+        JsonValue::Bool(value)
+    }
+}
+
+impl From<Vec<JsonValue>> for JsonValue {
+    fn from(values: Vec<JsonValue>) -> Self {
+        JsonValue::Array(values)
+    }
+}
+
+fn write_string(output: &mut String, value: &str) {
+    output.push('"');
+    for c in value.chars() {
+        match c {
+            '"' => output.push_str("\\\""),
+            '\\' => output.push_str("\\\\"),
+            '\n' => output.push_str("\\n"),
+            '\r' => output.push_str("\\r"),
+            '\t' => output.push_str("\\t"),
+            c if (c as u32) < 0x20 => {
+                let _ = write!(output, "\\u{:04x}", c as u32);
+            }
+            c => output.push(c),
+        }
+    }
+    output.push('"');
+}
+
+/// Deepest nesting of arrays and objects accepted, parsing recurses once per level so
+/// this keeps deeply nested input from overflowing the stack
+const MAX_DEPTH: usize = 512;
+
+struct Parser {
+    chars: Vec<char>,
+    position: usize,
+    depth: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.position).copied()
+    }
+
+    fn skip_whitespace(&mut self) {
+        while let Some(c) = self.peek() {
+            if !c.is_whitespace() {
+                break;
+            }
+            self.position += 1;
+        }
+    }
+
+    fn expect(&mut self, expected: char) -> Result<(), String> {
+        if self.peek() == Some(expected) {
+            self.position += 1;
+            Ok(())
+        } else {
+            Err(format!(
+                "expected '{}' at position {}",
+                expected, self.position
+            ))
+        }
+    }
+
+    fn expect_word(&mut self, word: &str, value: JsonValue) -> Result<JsonValue, String> {
+        for expected in word.chars() {
+            self.expect(expected)?;
+        }
+        Ok(value)
+    }
+
+    fn parse_value(&mut self) -> Result<JsonValue, String> {
+        self.skip_whitespace();
+        match self.peek() {
+            Some('{') => self.parse_nested(Parser::parse_object),
+            Some('[') => self.parse_nested(Parser::parse_array),
+            Some('"') => Ok(JsonValue::String(self.parse_string()?)),
+            Some('t') => self.expect_word("true", JsonValue::Bool(true)),
+            Some('f') => self.expect_word("false", JsonValue::Bool(false)),
+            Some('n') => self.expect_word("null", JsonValue::Null),
+            Some(c) if c == '-' || c.is_ascii_digit() => self.parse_number(),
+            Some(c) => Err(format!(
+                "unexpected character '{}' at position {}",
+                c, self.position
+            )),
+            None => Err("unexpected end of input".to_string()),
+        }
+    }
+
+    fn parse_nested(
+        &mut self,
+        parse: fn(&mut Parser) -> Result<JsonValue, String>,
+    ) -> Result<JsonValue, String> {
+        if self.depth == MAX_DEPTH {
+            return Err(format!(
+                "nested deeper than {} levels at position {}",
+                MAX_DEPTH, self.position
+            ));
+        }
+
+        self.depth += 1;
+        let value = parse(self);
+        self.depth -= 1;
+        value
+    }
+
+    fn parse_object(&mut self) -> Result<JsonValue, String> {
+        self.expect('{')?;
+        let mut entries = Vec::new();
+        self.skip_whitespace();
+        if self.peek() == Some('}') {
+            self.position += 1;
+            return Ok(JsonValue::Object(entries));
+        }
+        loop {
+            self.skip_whitespace();
+            let key = self.parse_string()?;
+            self.skip_whitespace();
+            self.expect(':')?;
+            let value = self.parse_value()?;
+            entries.push((key, value));
+            self.skip_whitespace();
+            match self.peek() {
+                Some(',') => self.position += 1,
+                Some('}') => {
+                    self.position += 1;
+                    return Ok(JsonValue::Object(entries));
+                }
+                _ => {
+                    return Err(format!(
+                        "expected ',' or '}}' at position {}",
+                        self.position
+                    ))
+                }
+            }
+        }
+    }
+
+    fn parse_array(&mut self) -> Result<JsonValue, String> {
+        self.expect('[')?;
+        let mut values = Vec::new();
+        self.skip_whitespace();
+        if self.peek() == Some(']') {
+            self.position += 1;
+            return Ok(JsonValue::Array(values));
+        }
+        loop {
+            values.push(self.parse_value()?);
+            self.skip_whitespace();
+            match self.peek() {
+                Some(',') => self.position += 1,
+                Some(']') => {
+                    self.position += 1;
+                    return Ok(JsonValue::Array(values));
+                }
+                _ => return Err(format!("expected ',' or ']' at position {}", self.position)),
+            }
+        }
+    }
+
+    fn parse_string(&mut self) -> Result<String, String> {
+        self.expect('"')?;
+        let mut value = String::new();
+        loop {
+            let c = self.peek().ok_or("unterminated string")?;
+            self.position += 1;
+            match c {
+                '"' => return Ok(value),
+                '\\' => {
+                    let escaped = self.peek().ok_or("unterminated escape sequence")?;
+                    self.position += 1;
+                    match escaped {
+                        '"' => value.push('"'),
+                        '\\' => value.push('\\'),
+                        '/' => value.push('/'),
+                        'b' => value.push('\u{0008}'),
+                        'f' => value.push('\u{000c}'),
+                        'n' => value.push('\n'),
+                        'r' => value.push('\r'),
+                        't' => value.push('\t'),
+                        'u' => {
+                            let start = self.position - 2;
+                            let mut code = self.parse_hex_escape()?;
+                            // Combine UTF-16 surrogate pairs, a surrogate on its own is not
+                            // a character
+                            if (0xD800..0xDC00).contains(&code)
+                                && self.chars.get(self.position) == Some(&'\\')
+                                && self.chars.get(self.position + 1) == Some(&'u')
+                            {
+                                self.position += 2;
+                                let low = self.parse_hex_escape()?;
+                                if !(0xDC00..0xE000).contains(&low) {
+                                    return Err(format!(
+                                        "invalid low surrogate \\u{:04x} at position {}",
+                                        low, self.position
+                                    ));
+                                }
+                                code = 0x10000 + ((code - 0xD800) << 10) + (low - 0xDC00);
+                            }
+                            match char::from_u32(code) {
+                                Some(c) => value.push(c),
+                                None => {
+                                    return Err(format!(
+                                        "unpaired surrogate \\u{:04x} at position {}",
+                                        code, start
+                                    ))
+                                }
+                            }
+                        }
+                        _ => {
+                            return Err(format!(
+                                "invalid escape sequence at position {}",
+                                self.position
+                            ))
+                        }
+                    }
+                }
+                c => value.push(c),
+            }
+        }
+    }
+
+    fn parse_hex_escape(&mut self) -> Result<u32, String> {
+        if self.position + 4 > self.chars.len() {
+            return Err("unterminated unicode escape".to_string());
+        }
+        let hex: String = self.chars[self.position..self.position + 4]
+            .iter()
+            .collect();
+        self.position += 4;
+        // from_str_radix would also accept a leading sign
+        if !hex.chars().all(|c| c.is_ascii_hexdigit()) {
+            return Err(format!("invalid unicode escape \\u{}", hex));
+        }
+        u32::from_str_radix(&hex, 16).map_err(|_| format!("invalid unicode escape \\u{}", hex))
+    }
+
+    fn parse_number(&mut self) -> Result<JsonValue, String> {
+        let start = self.position;
+        while let Some(c) = self.peek() {
+            if c.is_ascii_digit() || matches!(c, '-' | '+' | '.' | 'e' | 'E') {
+                self.position += 1;
+            } else {
+                break;
+            }
+        }
+        let number: String = self.chars[start..self.position].iter().collect();
+        number
+            .parse::<f64>()
+            .map(JsonValue::Number)
+            .map_err(|_| format!("invalid number {} at position {}", number, start))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::JsonValue;
+
+    #[test]
+    fn parses_values() {
+        let value = JsonValue::parse(r#" {"a": [1, -2.5e1, true, false, null], "b": "x"} "#);
+        assert_eq!(
+            value,
+            Ok(JsonValue::object()
+                .with(
+                    "a",
+                    JsonValue::Array(vec![
+                        JsonValue::Number(1.0),
+                        JsonValue::Number(-25.0),
+                        JsonValue::Bool(true),
+                        JsonValue::Bool(false),
+                        JsonValue::Null,
+                    ])
+                )
+                .with("b", JsonValue::String("x".to_string())))
+        );
+        assert_eq!(JsonValue::parse("[]"), Ok(JsonValue::Array(Vec::new())));
+        assert_eq!(JsonValue::parse("{}"), Ok(JsonValue::object()));
+    }
+
+    #[test]
+    fn parses_escapes() {
+        assert_eq!(
+            JsonValue::parse(r#""\"\\\/\b\f\n\r\té""#),
+            Ok(JsonValue::String("\"\\/\u{8}\u{c}\n\r\té".to_string()))
+        );
+        assert_eq!(
+            JsonValue::parse(r#""\ud83d\ude00""#),
+            Ok(JsonValue::String("😀".to_string()))
+        );
+    }
+
+    #[test]
+    fn rejects_unpaired_surrogates() {
+        for input in [
+            r#""\ud800""#,
+            r#""\ud800x""#,
+            r#""\udc00""#,
+            r#""\ud800A""#,
+            r#""\ud800\ud800""#,
+        ] {
+            assert!(JsonValue::parse(input).is_err(), "{}", input);
+        }
+    }
+
+    #[test]
+    fn rejects_malformed_input() {
+        for input in [
+            "",
+            "[1,]",
+            "[1 2]",
+            r#"{"a" 1}"#,
+            r#"{"a": 1,}"#,
+            "tru",
+            "nul",
+            "1 2",
+            r#""unterminated"#,
+            r#""\x""#,
+            r#""\u12""#,
+            r#""\u+123""#,
+            r#""\u-123""#,
+            "-",
+        ] {
+            assert!(JsonValue::parse(input).is_err(), "{}", input);
+        }
+    }
+
+    #[test]
+    fn limits_nesting() {
+        let nested = |depth: usize| format!("{}{}", "[".repeat(depth), "]".repeat(depth));
+        assert!(JsonValue::parse(&nested(512)).is_ok());
+        assert!(JsonValue::parse(&nested(513)).is_err());
+        assert!(JsonValue::parse(&nested(100_000)).is_err());
+    }
+
+    #[test]
+    fn round_trips_strings() {
+        let value = JsonValue::String("tab\tquote\"newline\n\u{1}é".to_string());
+        assert_eq!(JsonValue::parse(&value.to_compact_string()), Ok(value));
+    }
+}
@@ -0,0 +1,557 @@
+use crate::scan::{DirectoryObject, FileObject};
+use crate::styles::TextStyle;
+use std::collections::HashMap;
+use std::fs::{self, File};
+use std::io::Read;
+
+/// Number of bytes checked for null bytes when deciding if a file is binary
+const BINARY_CHECK_LENGTH: usize = 8000;
+
+pub struct Language {
+    pub name: &'static str,
+    extensions: &'static [&'static str],
+    file_names: &'static [&'static str],
+    interpreters: &'static [&'static str],
+    line_comments: &'static [&'static str],
+    block_comments: &'static [(&'static str, &'static str)],
+}
+
+const C_BLOCK: &[(&str, &str)] = &[("/*", "*/")];
+
+const LANGUAGES: &[Language] = &[
+    Language {
+        name: "Rust",
+        extensions: &["rs"],
+        file_names: &[],
+        interpreters: &[],
+        line_comments: &["//"],
+        block_comments: C_BLOCK,
+    },
+    Language {
+        name: "C",
+        extensions: &["c", "h"],
+        file_names: &[],
+        interpreters: &[],
+        line_comments: &["//"],
+        block_comments: C_BLOCK,
+    },
+    Language {
+        name: "C++",
+        extensions: &["cpp", "cc", "cxx", "hpp", "hh", "hxx"],
+        file_names: &[],
+        interpreters: &[],
+        line_comments: &["//"],
+        block_comments: C_BLOCK,
+    },
+    Language {
+        name: "C#",
+        extensions: &["cs"],
+        file_names: &[],
+        interpreters: &[],
+        line_comments: &["//"],
+        block_comments: C_BLOCK,
+    },
+    Language {
+        name: "Go",
+        extensions: &["go"],
+        file_names: &[],
+        interpreters: &[],
+        line_comments: &["//"],
+        block_comments: C_BLOCK,
+    },
+    Language {
+        name: "Java",
+        extensions: &["java"],
+        file_names: &[],
+        interpreters: &[],
+        line_comments: &["//"],
+        block_comments: C_BLOCK,
+    },
+    Language {
+        name: "Kotlin",
+        extensions: &["kt", "kts"],
+        file_names: &[],
+        interpreters: &[],
+        line_comments: &["//"],
+        block_comments: C_BLOCK,
+    },
+    Language {
+        name: "Scala",
+        extensions: &["scala", "sc"],
+        file_names: &[],
+        interpreters: &[],
+        line_comments: &["//"],
+        block_comments: C_BLOCK,
+    },
+    Language {
+        name: "Swift",
+        extensions: &["swift"],
+        file_names: &[],
+        interpreters: &[],
+        line_comments: &["//"],
+        block_comments: C_BLOCK,
+    },
+    Language {
+        name: "Dart",
+        extensions: &["dart"],
+        file_names: &[],
+        interpreters: &[],
+        line_comments: &["//"],
+        block_comments: C_BLOCK,
+    },
+    Language {
+        name: "Zig",
+        extensions: &["zig"],
+        file_names: &[],
+        interpreters: &[],
+        line_comments: &["//"],
+        block_comments: &[],
+    },
+    Language {
+        name: "JavaScript",
+        extensions: &["js", "mjs", "cjs", "jsx"],
+        file_names: &[],
+        interpreters: &["node", "deno", "bun"],
+        line_comments: &["//"],
+        block_comments: C_BLOCK,
+    },
+    Language {
+        name: "TypeScript",
+        extensions: &["ts", "mts", "cts", "tsx"],
+        file_names: &[],
+        interpreters: &["ts-node"],
+        line_comments: &["//"],
+        block_comments: C_BLOCK,
+    },
+    Language {
+        name: "CSS",
+        extensions: &["css", "scss", "sass", "less"],
+        file_names: &[],
+        interpreters: &[],
+        line_comments: &[],
+        block_comments: C_BLOCK,
+    },
+    Language {
+        name: "HTML",
+        extensions: &["html", "htm", "xhtml", "vue", "svelte"],
+        file_names: &[],
+        interpreters: &[],
+        line_comments: &[],
+        block_comments: &[("<!--", "-->")],
+    },
+    Language {
+        name: "XML",
+        extensions: &["xml", "svg", "xsd", "xsl", "plist"],
+        file_names: &[],
+        interpreters: &[],
+        line_comments: &[],
+        block_comments: &[("<!--", "-->")],
+    },
+    Language {
+        name: "Python",
+        extensions: &["py", "pyw", "pyi"],
+        file_names: &[],
+        interpreters: &["python", "python2", "python3"],
+        line_comments: &["#"],
+        block_comments: &[("\"\"\"", "\"\"\""), ("'''", "'''")],
+    },
+    Language {
+        name: "Ruby",
+        extensions: &["rb", "rake", "gemspec"],
+        file_names: &["Gemfile", "Rakefile"],
+        interpreters: &["ruby"],
+        line_comments: &["#"],
+        block_comments: &[("=begin", "=end")],
+    },
+    Language {
+        name: "Perl",
+        extensions: &["pl", "pm"],
+        file_names: &[],
+        interpreters: &["perl"],
+        line_comments: &["#"],
+        block_comments: &[("=pod", "=cut")],
+    },
+    Language {
+        name: "PHP",
+        extensions: &["php"],
+        file_names: &[],
+        interpreters: &["php"],
+        line_comments: &["//", "#"],
+        block_comments: C_BLOCK,
+    },
+    Language {
+        name: "Shell",
+        extensions: &["sh", "bash", "zsh", "fish", "ksh"],
+        file_names: &[".bashrc", ".zshrc", ".profile", ".zprofile"],
+        interpreters: &["sh", "bash", "zsh", "fish", "ksh", "dash"],
+        line_comments: &["#"],
+        block_comments: &[],
+    },
+    Language {
+        name: "PowerShell",
+        extensions: &["ps1", "psm1"],
+        file_names: &[],
+        interpreters: &["pwsh", "powershell"],
+        line_comments: &["#"],
+        block_comments: &[("<#", "#>")],
+    },
+    Language {
+        name: "Lua",
+        extensions: &["lua"],
+        file_names: &[],
+        interpreters: &["lua"],
+        line_comments: &["--"],
+        block_comments: &[("--[[", "]]")],
+    },
+    Language {
+        name: "SQL",
+        extensions: &["sql"],
+        file_names: &[],
+        interpreters: &[],
+        line_comments: &["--"],
+        block_comments: C_BLOCK,
+    },
+    Language {
+        name: "Haskell",
+        extensions: &["hs"],
+        file_names: &[],
+        interpreters: &["runhaskell"],
+        line_comments: &["--"],
+        block_comments: &[("{-", "-}")],
+    },
+    Language {
+        name: "Elixir",
+        extensions: &["ex", "exs"],
+        file_names: &[],
+        interpreters: &["elixir"],
+        line_comments: &["#"],
+        block_comments: &[],
+    },
+    Language {
+        name: "Erlang",
+        extensions: &["erl", "hrl"],
+        file_names: &[],
+        interpreters: &["escript"],
+        line_comments: &["%"],
+        block_comments: &[],
+    },
+    Language {
+        name: "R",
+        extensions: &["r"],
+        file_names: &[],
+        interpreters: &["Rscript"],
+        line_comments: &["#"],
+        block_comments: &[],
+    },
+    Language {
+        name: "TOML",
+        extensions: &["toml"],
+        file_names: &[],
+        interpreters: &[],
+        line_comments: &["#"],
+        block_comments: &[],
+    },
+    Language {
+        name: "YAML",
+        extensions: &["yml", "yaml"],
+        file_names: &[],
+        interpreters: &[],
+        line_comments: &["#"],
+        block_comments: &[],
+    },
+    Language {
+        name: "JSON",
+        extensions: &["json"],
+        file_names: &[],
+        interpreters: &[],
+        line_comments: &[],
+        block_comments: &[],
+    },
+    Language {
+        name: "Markdown",
+        extensions: &["md", "markdown"],
+        file_names: &[],
+        interpreters: &[],
+        line_comments: &[],
+        block_comments: &[("<!--", "-->")],
+    },
+    Language {
+        name: "Makefile",
+        extensions: &["mk"],
+        file_names: &["Makefile", "makefile", "GNUmakefile"],
+        interpreters: &["make"],
+        line_comments: &["#"],
+        block_comments: &[],
+    },
+    Language {
+        name: "Dockerfile",
+        extensions: &["dockerfile"],
+        file_names: &["Dockerfile", "Containerfile"],
+        interpreters: &[],
+        line_comments: &["#"],
+        block_comments: &[],
+    },
+];
+
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct LineCounts {
+    pub code: u64,
+    pub comment: u64,
+    pub blank: u64,
+}
+
+impl LineCounts {
+    pub fn add(&mut self, other: &LineCounts) {
+        self.code += other.code;
+        self.comment += other.comment;
+        self.blank += other.blank;
+    }
+
+    pub fn total(&self) -> u64 {
+        self.code + self.comment + self.blank
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct CodeStats {
+    pub language: &'static str,
+    pub lines: LineCounts,
+}
+
+/// Find the language of a file from its name, extension or shebang line
+pub fn detect_language(file: &FileObject) -> Option<&'static Language> {
+    let ending = file.ending.to_lowercase();
+
+    LANGUAGES
+        .iter()
+        .find(|language| language.file_names.contains(&file.full_name.as_str()))
+        .or_else(|| {
+            if ending.is_empty() {
+                return None;
+            }
+            LANGUAGES
+                .iter()
+                .find(|language| language.extensions.contains(&ending.as_str()))
+        })
+        .or_else(|| {
+            if !ending.is_empty() {
+                return None;
+            }
+            let interpreter = read_shebang_interpreter(&file.path)?;
+            LANGUAGES
+                .iter()
+                .find(|language| language.interpreters.contains(&interpreter.as_str()))
+        })
+}
+
+fn read_shebang_interpreter(path: &str) -> Option<String> {
+    let mut buffer = [0u8; 128];
+    let length = File::open(path).ok()?.read(&mut buffer).ok()?;
+    let first_line = String::from_utf8_lossy(&buffer[..length])
+        .lines()
+        .next()?
+        .to_string();
+    let command = first_line.strip_prefix("#!")?;
+
+    let mut parts = command.split_whitespace();
+    let mut program = parts.next()?.rsplit('/').next()?;
+    if program == "env" {
+        program = parts.find(|part| !part.starts_with('-'))?;
+    }
+
+    // python3.11 and similar versioned interpreters
+    let program = program.trim_end_matches(|c: char| c.is_ascii_digit() || c == '.');
+    Some(program.to_string())
+}
+
+/// Read a file as text, returns none for binary files, which are detected by a NUL byte
+/// near the start of the file
+pub fn read_text_file(path: &str) -> Option<String> {
+    let content = fs::read(path).ok()?;
+    let check_length = content.len().min(BINARY_CHECK_LENGTH);
+    if content[..check_length].contains(&0) {
+        return None;
+    }
+
+    Some(String::from_utf8_lossy(&content).to_string())
+}
+
+/// Count the code, comment and blank lines of a file, returns none for binary files
+pub fn count_lines(path: &str, language: &Language) -> Option<LineCounts> {
+    let content = read_text_file(path)?;
+    let mut counts = LineCounts::default();
+    let mut open_block: Option<&str> = None;
+
+    for line in content.lines() {
+        let line = line.trim();
+
+        if let Some(block_end) = open_block {
+            counts.comment += 1;
+            if line.contains(block_end) {
+                open_block = None;
+            }
+            continue;
+        }
+
+        if line.is_empty() {
+            counts.blank += 1;
+            continue;
+        }
+
+        if language
+            .line_comments
+            .iter()
+            .any(|comment| line.starts_with(comment))
+            && !language
+                .block_comments
+                .iter()
+                .any(|(start, _)| line.starts_with(start))
+        {
+            counts.comment += 1;
+            continue;
+        }
+
+        let mut is_comment = false;
+        for (start, end) in language.block_comments {
+            if let Some(start_index) = line.find(start) {
+                let after_start = &line[start_index + start.len()..];
+                if !after_start.contains(end) {
+                    open_block = Some(end);
+                }
+                is_comment = start_index == 0;
+                break;
+            }
+        }
+
+        if is_comment {
+            counts.comment += 1;
+        } else {
+            counts.code += 1;
+        }
+    }
+
+    Some(counts)
+}
+
+/// Count lines for every file with a known language, directories get the totals of
+/// everything inside them
+pub fn annotate_line_counts(directory: &mut DirectoryObject) -> LineCounts {
+    let mut totals = LineCounts::default();
+
+    for file in directory.files.iter_mut() {
+        file.code_stats = detect_language(file).and_then(|language| {
+            count_lines(&file.path, language).map(|lines| CodeStats {
+                language: language.name,
+                lines,
+            })
+        });
+
+        if let Some(code_stats) = &file.code_stats {
+            totals.add(&code_stats.lines);
+        }
+    }
+
+    for sub_directory in directory.directories.iter_mut() {
+        totals.add(&annotate_line_counts(sub_directory));
+    }
+
+    directory.line_counts = Some(totals);
+    totals
+}
+
+pub fn format_line_counts(lines: &LineCounts) -> String {
+    format!(
+        "{} code, {} comment, {} blank",
+        lines.code, lines.comment, lines.blank
+    )
+}
+
+pub struct LanguageSummary {
+    pub language: &'static str,
+    pub files: u64,
+    pub lines: LineCounts,
+}
+
+pub fn summarize_languages(tree: &DirectoryObject) -> Vec<LanguageSummary> {
+    let mut summaries: HashMap<&'static str, LanguageSummary> = HashMap::new();
+    add_language_summaries(tree, &mut summaries);
+
+    let mut summaries: Vec<LanguageSummary> = summaries.into_values().collect();
+    summaries.sort_by(|a, b| {
+        b.lines
+            .code
+            .cmp(&a.lines.code)
+            .then(a.language.cmp(b.language))
+    });
+    summaries
+}
+
+fn add_language_summaries(
+    directory: &DirectoryObject,
+    summaries: &mut HashMap<&'static str, LanguageSummary>,
+) {
+    for code_stats in directory.files.iter().filter_map(|file| file.code_stats) {
+        let summary = summaries
+            .entry(code_stats.language)
+            .or_insert(LanguageSummary {
+                language: code_stats.language,
+                files: 0,
+                lines: LineCounts::default(),
+            });
+        summary.files += 1;
+        summary.lines.add(&code_stats.lines);
+    }
+
+    for sub_directory in &directory.directories {
+        add_language_summaries(sub_directory, summaries);
+    }
+}
+
+pub fn display_language_summary(summaries: &[LanguageSummary]) {
+    let language_width = summaries
+        .iter()
+        .map(|summary| summary.language.len())
+        .max()
+        .unwrap_or(0)
+        .max("language".len());
+
+    println!(
+        "{}{:<language_width$} {:>7} {:>10} {:>10} {:>10} {:>10}{}",
+        TextStyle::Bold.to_ansi_style_string(),
+        "language",
+        "files",
+        "code",
+        "comment",
+        "blank",
+        "total",
+        TextStyle::Reset.to_ansi_style_string()
+    );
+
+    let mut totals = LineCounts::default();
+    let mut total_files = 0;
+    for summary in summaries {
+        println!(
+            "{:<language_width$} {:>7} {:>10} {:>10} {:>10} {:>10}",
+            summary.language,
+            summary.files,
+            summary.lines.code,
+            summary.lines.comment,
+            summary.lines.blank,
+            summary.lines.total()
+        );
+        totals.add(&summary.lines);
+        total_files += summary.files;
+    }
+
+    println!(
+        "{}{:<language_width$} {:>7} {:>10} {:>10} {:>10} {:>10}{}",
+        TextStyle::Bold.to_ansi_style_string(),
+        "total",
+        total_files,
+        totals.code,
+        totals.comment,
+        totals.blank,
+        totals.total(),
+        TextStyle::Reset.to_ansi_style_string()
+    );
+}
@@ -1,22 +1,75 @@
+pub mod browse;
+pub mod charset;
 pub mod cli;
+pub mod colors;
+pub mod diff;
 pub mod display;
+pub mod dupes;
+pub mod filter;
+pub mod glob;
+pub mod grep;
+pub mod icons;
+pub mod json;
+pub mod loc;
+pub mod metadata;
+pub mod regex;
+pub mod report;
 pub mod scan;
+pub mod size;
+pub mod snapshot;
+pub mod stats;
 pub mod styles;
+pub mod theme;
+pub mod top;
+pub mod usage;
 pub mod utils;
+pub mod watch;
 pub mod system {
     pub mod config;
     pub mod local;
+    pub mod notify;
+    pub mod terminal;
 }
 
-use crate::display::display;
-use crate::scan::scan;
+use crate::browse::browse;
+use crate::charset::{set_charset, Charset};
+use crate::diff::{diff_to_json, diff_trees, display_diff};
+use crate::display::{display, BarScale, DisplayOptions, PathMode};
+use crate::dupes::{
+    display_duplicates, duplicate_annotations, duplicates_to_json, find_duplicates,
+};
+use crate::filter::{
+    compact_directories, prune_empty_directories, retain_entries, ModifiedWindow, PathPatterns,
+    SizeRange,
+};
+use crate::grep::{match_annotations, search_tree, total_matches};
+use crate::icons::Icons;
+use crate::json::JsonValue;
+use crate::loc::{annotate_line_counts, display_language_summary, summarize_languages};
+use crate::metadata::{parse_time_reference, TimeField, DEFAULT_TIME_FORMAT};
+use crate::regex::Regex;
+use crate::report::{display_report, report_to_json, ScanReport};
+use crate::scan::{scan, DirectoryObject};
+use crate::size::{parse_size, set_size_format, SizeFormat, SizeUnits, DEFAULT_PRECISION};
+use crate::snapshot::{load_tree, save_snapshot, snapshot_to_json};
+use crate::stats::{collect_extension_stats, display_stats, stats_to_json, StatsSortOrder};
+use crate::styles::{colors_enabled, set_color_choice, ColorChoice};
 use crate::system::config::{
     add_value_to_setting, create_config_file, get_setting_from_config, remove_value_from_setting,
     ConfigOption,
 };
 use crate::system::local::install;
-use crate::utils::get_current_directory_path;
+use crate::theme::Theme;
+use crate::top::{display_top, find_largest, top_to_json};
+use crate::usage::{explore_usage, export_ncdu, import_ncdu};
+use crate::utils::{
+    exit_with_error, get_current_directory_path, parse_extension_list, relative_path,
+};
+use crate::watch::watch;
 use cli::{Arg, Cli, CmdOption, Command};
+use std::collections::{HashMap, HashSet};
+use std::io::{self, IsTerminal};
+use std::time::{Duration, Instant, SystemTime};
 
 #[derive(Debug)]
 pub enum OS {
@@ -44,6 +97,42 @@ fn main() {
 
     let cli = Cli::new()
         .with_default_command("scan")
+        .with_global_arg(
+            Arg::long_only("color", "Use colors, auto, always or never", "color")
+                .with_value_name("WHEN")
+                .with_default_value("auto"),
+        )
+        .with_global_arg(
+            Arg::long_only(
+                "charset",
+                "Characters of tree lines, ascii, unicode, heavy, rounded or double",
+                "charset",
+            )
+            .with_value_name("CHARSET"),
+        )
+        .with_global_arg(Arg::long_only(
+            "si",
+            "Show sizes in powers of 1000, like kB and MB",
+            "si",
+        ))
+        .with_global_arg(Arg::long_only(
+            "iec",
+            "Show sizes in powers of 1024, like KiB and MiB",
+            "iec",
+        ))
+        .with_global_arg(Arg::long_only(
+            "bytes",
+            "Show exact sizes in bytes",
+            "bytes",
+        ))
+        .with_global_arg(
+            Arg::long_only(
+                "precision",
+                "Number of decimals shown in sizes",
+                "precision",
+            )
+            .with_value_name("N"),
+        )
         .with_command(Command::new("help", "Prints help information").with_option(
             CmdOption::new("command", "COMMAND", "The command you want help with").optional(),
         ))
@@ -82,26 +171,245 @@ fn main() {
             ),
         )
         .with_command(
-            Command::new("scan", "Scan a directory")
+            with_display_args(with_tree_args(
+                Command::new("scan", "Scan a directory")
+                    .with_option(CmdOption::new("path", "PATH", "path to a directory").optional()),
+            ))
+            .with_arg(Arg::new(
+                "lines",
+                "Count lines of code and show a summary by language",
+                "lines",
+                'l',
+            ))
+            .with_arg(Arg::long_only(
+                "no report",
+                "Hide the totals shown below the tree",
+                "no-report",
+            ))
+            .with_arg(
+                Arg::long_only(
+                    "newer than",
+                    "Only show files modified after an age (2h, 3d) or date",
+                    "newer-than",
+                )
+                .with_value_name("WHEN"),
+            )
+            .with_arg(
+                Arg::long_only(
+                    "older than",
+                    "Only show files modified before an age (2h, 3d) or date",
+                    "older-than",
+                )
+                .with_value_name("WHEN"),
+            )
+            .with_arg(
+                Arg::long_only(
+                    "min size",
+                    "Only show files of at least a size, like 10K or 1.5MB",
+                    "min-size",
+                )
+                .with_value_name("SIZE"),
+            )
+            .with_arg(
+                Arg::long_only(
+                    "max size",
+                    "Only show files of at most a size, like 10K or 1.5MB",
+                    "max-size",
+                )
+                .with_value_name("SIZE"),
+            )
+            .with_arg(
+                Arg::long_only(
+                    "include",
+                    "Only show files matching a glob, can be repeated",
+                    "include",
+                )
+                .with_value_name("GLOB"),
+            )
+            .with_arg(
+                Arg::long_only(
+                    "exclude",
+                    "Hide files and directories matching a glob, can be repeated",
+                    "exclude",
+                )
+                .with_value_name("GLOB"),
+            ),
+        )
+        .with_command(with_toggle_args(with_tree_args(
+            Command::new("browse", "Browse a directory in an interactive tree")
+                .with_option(CmdOption::new("path", "PATH", "path to a directory").optional()),
+        )))
+        .with_command(
+            Command::new("top", "List the largest files and directories")
                 .with_option(CmdOption::new("path", "PATH", "path to a directory").optional())
+                .with_arg(
+                    Arg::new("count", "Number of entries to show", "count", 'n')
+                        .with_value_name("N")
+                        .with_default_value("10"),
+                )
+                .with_arg(
+                    Arg::new(
+                        "extensions",
+                        "Only count files with these extensions (rs,toml)",
+                        "extensions",
+                        'e',
+                    )
+                    .with_value_name("LIST"),
+                )
+                .with_arg(Arg::new("json", "Output the report as JSON", "json", 'j')),
+        )
+        .with_command(
+            with_display_args(with_tree_args(
+                Command::new("grep", "Show the files whose content matches a pattern")
+                    .with_option(CmdOption::new(
+                        "pattern",
+                        "PATTERN",
+                        "regular expression to search for",
+                    ))
+                    .with_option(CmdOption::new("path", "PATH", "path to a directory").optional()),
+            ))
+            .with_arg(Arg::new(
+                "ignore case",
+                "Match upper and lower case letters alike",
+                "ignore-case",
+                'i',
+            ))
+            .with_arg(Arg::new(
+                "first line",
+                "Show the first matching line of each file",
+                "first-line",
+                'm',
+            )),
+        )
+        .with_command(
+            with_display_args(with_tree_args(
+                Command::new("dupes", "Find files with identical content")
+                    .with_option(CmdOption::new("path", "PATH", "path to a directory").optional()),
+            ))
+            .with_arg(Arg::new(
+                "tree",
+                "Show the tree with duplicates marked",
+                "tree",
+                't',
+            ))
+            .with_arg(Arg::new(
+                "json",
+                "Output the duplicates as JSON",
+                "json",
+                'j',
+            )),
+        )
+        .with_command(
+            Command::new("stats", "Show statistics for each file extension")
+                .with_option(CmdOption::new("path", "PATH", "path to a directory").optional())
+                .with_arg(
+                    Arg::new("sort", "Sort by size or count", "sort", 's')
+                        .with_value_name("ORDER")
+                        .with_default_value("size"),
+                )
                 .with_arg(Arg::new(
-                    "no extentions",
-                    "Disable file extentions",
-                    "",
-                    'x',
-                ))
-                .with_arg(Arg::new("file sizes", "Show file sizes", "", 'f'))
-                .with_arg(Arg::new("directory sizes", "Show directory sizes", "", 'd'))
-                .with_arg(Arg::new(
-                    "file count",
-                    "Show the file count in a directory",
-                    "",
-                    'c',
+                    "json",
+                    "Output the statistics as JSON",
+                    "json",
+                    'j',
                 )),
+        )
+        .with_command(
+            Command::new("usage", "Explore disk usage sorted by size")
+                .with_option(CmdOption::new("path", "PATH", "path to a directory").optional())
+                .with_arg(
+                    Arg::new(
+                        "export",
+                        "Write the scan as ncdu JSON instead of exploring, - for stdout",
+                        "export",
+                        'e',
+                    )
+                    .with_value_name("FILE"),
+                )
+                .with_arg(
+                    Arg::new(
+                        "import",
+                        "Read an ncdu JSON export instead of scanning",
+                        "import",
+                        'i',
+                    )
+                    .with_value_name("FILE"),
+                ),
+        )
+        .with_command(
+            with_display_args(
+                Command::new(
+                    "watch",
+                    "Scan a directory and redraw the tree when it changes",
+                )
+                .with_option(CmdOption::new("path", "PATH", "path to a directory").optional()),
+            )
+            .with_arg(
+                Arg::new(
+                    "interval",
+                    "Seconds between checks for changes",
+                    "interval",
+                    'i',
+                )
+                .with_value_name("SECONDS")
+                .with_default_value("1"),
+            ),
+        )
+        .with_command(
+            Command::new("snapshot", "Save a scan of a directory to a snapshot file")
+                .with_option(CmdOption::new("path", "PATH", "path to a directory").optional())
+                .with_arg(
+                    Arg::new(
+                        "output",
+                        "File to write the snapshot to, prints to stdout if not set",
+                        "output",
+                        'o',
+                    )
+                    .with_value_name("FILE"),
+                ),
+        )
+        .with_command(
+            Command::new("diff", "Compare two directories or snapshots")
+                .with_option(CmdOption::new(
+                    "old",
+                    "OLD",
+                    "path to the old directory or snapshot",
+                ))
+                .with_option(CmdOption::new(
+                    "new",
+                    "NEW",
+                    "path to the new directory or snapshot",
+                ))
+                .with_arg(Arg::new("all", "Show unchanged entries", "all", 'a'))
+                .with_arg(Arg::new("json", "Output the diff as JSON", "json", 'j')),
         );
 
+    let color_choice = match ColorChoice::from_string(&cli.get_global_arg("color").throw_if_none())
+    {
+        Some(color_choice) => color_choice,
+        None => {
+            exit_with_error("color must be \"auto\", \"always\" or \"never\"", false);
+            return;
+        }
+    };
+    set_color_choice(color_choice, io::stdout().is_terminal());
+
+    match Charset::load(cli.get_global_arg("charset").to_option(), &os) {
+        Ok(charset) => set_charset(charset),
+        Err(e) => exit_with_error(&e, false),
+    }
+
+    set_size_format(get_size_format(&cli));
+
     let command = cli.match_commands();
 
+    // The interactive views draw on stderr and print the selected path to stdout
+    if command.name == "browse"
+        || (command.name == "usage" && command.get_arg("export").to_option().is_none())
+    {
+        set_color_choice(color_choice, io::stderr().is_terminal());
+    }
+
     match command.name.as_str() {
         "help" => {
             let command = command.get_option("command").to_option();
@@ -162,32 +470,605 @@ fn main() {
         }
         "scan" => {
             let raw_path = command.get_option("path").to_option();
-            let show_endings = command.has("no extentions");
-            let show_file_sizes = command.has("file sizes");
-            let show_dir_sizes = command.has("directory sizes");
-            let show_file_count = command.has("show_file_count");
+            let options = get_display_options(command, &os);
 
             let path = match raw_path {
                 Some(path) => path,
                 None => get_current_directory_path(),
             };
 
+            let modified_window = ModifiedWindow {
+                newer_than: get_time_reference_arg(command, "newer than"),
+                older_than: get_time_reference_arg(command, "older than"),
+            };
+
+            let path_patterns = PathPatterns {
+                include: command.get_args("include"),
+                exclude: command.get_args("exclude"),
+            };
+
+            let size_range = SizeRange {
+                min: get_size_arg(command, "min size"),
+                max: get_size_arg(command, "max size"),
+            };
+
+            let scan_start = Instant::now();
+            let mut tree = scan(&path, &os);
+            let mut report = ScanReport::from_tree(&tree, scan_start.elapsed());
+
+            if !modified_window.is_empty() || !path_patterns.is_empty() || !size_range.is_empty() {
+                let root_path = tree.path.clone();
+                retain_entries(
+                    &mut tree,
+                    &|file| {
+                        modified_window.contains(file)
+                            && size_range.contains(file.size)
+                            && path_patterns.matches(&relative_path(&root_path, &file.path))
+                    },
+                    &|directory| {
+                        !path_patterns.excludes(&relative_path(&root_path, &directory.path))
+                    },
+                );
+            }
+
+            transform_tree(command, &mut tree);
+
+            if options.show_line_counts {
+                annotate_line_counts(&mut tree);
+            }
+
+            display(&tree, &options, &os);
+
+            // Lists are meant for other tools, so only the paths are printed
+            if !command.has("no report") && !options.list {
+                report.count_entries(&tree);
+                display_report(&report);
+            }
+
+            if options.show_line_counts && !options.list {
+                println!();
+                display_language_summary(&summarize_languages(&tree));
+            }
+        }
+        "browse" => {
+            let options = get_display_options(command, &os);
+
+            let path = match command.get_option("path").to_option() {
+                Some(path) => path,
+                None => get_current_directory_path(),
+            };
+
+            let mut tree = scan(&path, &os);
+            transform_tree(command, &mut tree);
+
+            match browse(&tree, options) {
+                Ok(Some(selected_path)) => println!("{}", selected_path),
+                Ok(None) => {}
+                Err(e) => exit_with_error(&e, false),
+            }
+        }
+        "top" => {
+            let path = match command.get_option("path").to_option() {
+                Some(path) => path,
+                None => get_current_directory_path(),
+            };
+
+            let count = match command.get_arg("count").throw_if_none().parse::<usize>() {
+                Ok(count) => count,
+                Err(_) => {
+                    exit_with_error("count must be a positive whole number", false);
+                    return;
+                }
+            };
+
+            let extensions = match command.get_arg("extensions").to_option() {
+                Some(list) => parse_extension_list(&list),
+                None => Vec::new(),
+            };
+
+            let scan_start = Instant::now();
             let tree = scan(&path, &os);
+            let scan_report = ScanReport::from_tree(&tree, scan_start.elapsed());
+            let report = find_largest(&tree, count, &extensions);
 
-            display(
-                &tree,
-                &!show_endings,
-                &show_file_sizes,
-                &show_dir_sizes,
-                &show_file_count,
-                &os,
-            );
+            if command.has("json") {
+                let json = top_to_json(&report).with("report", report_to_json(&scan_report));
+                println!("{}", json.to_pretty_string());
+            } else {
+                display_top(&report);
+            }
+        }
+        "grep" => {
+            let pattern = command.get_option("pattern").throw_if_none();
+            let mut options = get_display_options(command, &os);
+
+            let path = match command.get_option("path").to_option() {
+                Some(path) => path,
+                None => get_current_directory_path(),
+            };
+
+            let regex = match Regex::new(&pattern, command.has("ignore case")) {
+                Ok(regex) => regex,
+                Err(e) => {
+                    exit_with_error(&format!("invalid pattern: {}", e), false);
+                    return;
+                }
+            };
+
+            let mut tree = scan(&path, &os);
+            let matches = search_tree(&tree, &regex);
+            retain_entries(&mut tree, &|file| matches.contains_key(&file.path), &|_| {
+                true
+            });
+            transform_tree(command, &mut tree);
+
+            options.annotations = match_annotations(&matches, command.has("first line"));
+            display(&tree, &options, &os);
+
+            if !options.list {
+                println!();
+                println!(
+                    "{} matches in {} files",
+                    total_matches(&matches),
+                    matches.len()
+                );
+            }
+        }
+        "dupes" => {
+            let path = match command.get_option("path").to_option() {
+                Some(path) => path,
+                None => get_current_directory_path(),
+            };
+
+            let scan_start = Instant::now();
+            let mut tree = scan(&path, &os);
+            let report = ScanReport::from_tree(&tree, scan_start.elapsed());
+            let groups = find_duplicates(&tree);
+
+            if command.has("json") {
+                let json =
+                    duplicates_to_json(&groups, &tree).with("report", report_to_json(&report));
+                println!("{}", json.to_pretty_string());
+            } else if command.has("tree") {
+                transform_tree(command, &mut tree);
+                let mut options = get_display_options(command, &os);
+                options.annotations = duplicate_annotations(&groups);
+                display(&tree, &options, &os);
+            } else {
+                display_duplicates(&groups, &tree);
+            }
+        }
+        "stats" => {
+            let path = match command.get_option("path").to_option() {
+                Some(path) => path,
+                None => get_current_directory_path(),
+            };
+
+            let sort_order =
+                match StatsSortOrder::from_string(&command.get_arg("sort").throw_if_none()) {
+                    Some(sort_order) => sort_order,
+                    None => {
+                        exit_with_error("sort must be \"size\" or \"count\"", false);
+                        return;
+                    }
+                };
+
+            let scan_start = Instant::now();
+            let tree = scan(&path, &os);
+            let report = ScanReport::from_tree(&tree, scan_start.elapsed());
+            let stats = collect_extension_stats(&tree, sort_order);
+
+            if command.has("json") {
+                let json = stats_to_json(&stats).with("report", report_to_json(&report));
+                println!("{}", json.to_pretty_string());
+            } else {
+                display_stats(&stats, sort_order);
+            }
+        }
+        "usage" => {
+            let tree = match command.get_arg("import").to_option() {
+                Some(import_path) => match import_ncdu(&import_path) {
+                    Ok(tree) => tree,
+                    Err(e) => {
+                        exit_with_error(&e, false);
+                        return;
+                    }
+                },
+                None => {
+                    let path = match command.get_option("path").to_option() {
+                        Some(path) => path,
+                        None => get_current_directory_path(),
+                    };
+                    scan(&path, &os)
+                }
+            };
+
+            let result = match command.get_arg("export").to_option() {
+                Some(export_path) => export_ncdu(&tree, &export_path),
+                None => explore_usage(&tree),
+            };
+
+            if let Err(e) = result {
+                exit_with_error(&e, false);
+            }
+        }
+        "watch" => {
+            let mut options = get_display_options(command, &os);
+
+            let path = match command.get_option("path").to_option() {
+                Some(path) => path,
+                None => get_current_directory_path(),
+            };
+
+            let interval = match command.get_arg("interval").throw_if_none().parse::<f64>() {
+                Ok(seconds) if seconds > 0.0 => Duration::from_secs_f64(seconds),
+                _ => {
+                    exit_with_error("interval must be a positive number of seconds", false);
+                    return;
+                }
+            };
+
+            watch(&path, &mut options, interval, &os);
+        }
+        "snapshot" => {
+            let path = match command.get_option("path").to_option() {
+                Some(path) => path,
+                None => get_current_directory_path(),
+            };
+
+            let scan_start = Instant::now();
+            let tree = scan(&path, &os);
+            let report = ScanReport::from_tree(&tree, scan_start.elapsed());
+
+            match command.get_arg("output").to_option() {
+                Some(output_path) => match save_snapshot(&tree, &report, &output_path) {
+                    Ok(()) => println!("saved snapshot of {} to {}", path, output_path),
+                    Err(e) => exit_with_error(&e, false),
+                },
+                None => println!("{}", snapshot_to_json(&tree, &report).to_pretty_string()),
+            }
+        }
+        "diff" => {
+            let old_source = command.get_option("old").throw_if_none();
+            let new_source = command.get_option("new").throw_if_none();
+
+            let ((old_tree, old_report), (new_tree, new_report)) =
+                match (load_tree(&old_source, &os), load_tree(&new_source, &os)) {
+                    (Ok(old), Ok(new)) => (old, new),
+                    (Err(e), _) | (_, Err(e)) => {
+                        exit_with_error(&e, false);
+                        return;
+                    }
+                };
+
+            let diff = diff_trees(&old_tree, &new_tree);
+
+            if command.has("json") {
+                // Each side of a diff has its own scan, or the one its snapshot was taken from
+                let json = diff_to_json(&diff, &old_source, &new_source).with(
+                    "report",
+                    JsonValue::object()
+                        .with("old", report_to_json(&old_report))
+                        .with("new", report_to_json(&new_report)),
+                );
+                println!("{}", json.to_pretty_string());
+            } else {
+                display_diff(&diff, command.has("all"));
+            }
         }
         _ => cli.help(None),
     }
 }
 
 fn recreate_config_file(os: &OS) {
-    create_config_file(&os, true);
+    create_config_file(os, true);
     println!("reset config file");
 }
+
+/// Add the arguments shared by every command that displays a tree
+fn with_display_args(command: Command) -> Command {
+    with_toggle_args(command)
+        .with_arg(Arg::new(
+            "permissions",
+            "Show the permissions of each entry",
+            "",
+            'p',
+        ))
+        .with_arg(Arg::new("owner", "Show the owner of each entry", "", 'u'))
+        .with_arg(Arg::new("group", "Show the group of each entry", "", 'g'))
+        .with_arg(Arg::new("date", "Show the date of each entry", "", 'D'))
+        .with_arg(
+            Arg::long_only(
+                "time field",
+                "Date shown by -D, modified, accessed, changed or created",
+                "timefield",
+            )
+            .with_value_name("FIELD")
+            .with_default_value("modified"),
+        )
+        .with_arg(
+            Arg::long_only(
+                "time format",
+                "strftime style format of dates shown by -D",
+                "timefmt",
+            )
+            .with_value_name("FORMAT")
+            .with_default_value(DEFAULT_TIME_FORMAT),
+        )
+        .with_arg(
+            Arg::long_only(
+                "max entries",
+                "Show the first N entries per directory by name and summarize the rest",
+                "max-entries",
+            )
+            .with_value_name("N"),
+        )
+        .with_arg(Arg::long_only(
+            "full path",
+            "Show the path of each entry relative to the scanned directory",
+            "full-path",
+        ))
+        .with_arg(Arg::long_only(
+            "absolute",
+            "Show the absolute path of each entry",
+            "absolute",
+        ))
+        .with_arg(Arg::long_only(
+            "list",
+            "Print one quoted path per line instead of a tree",
+            "list",
+        ))
+        .with_arg(Arg::long_only(
+            "bars",
+            "Show a bar with the share of the size of each entry",
+            "bars",
+        ))
+        .with_arg(
+            Arg::long_only(
+                "bar scale",
+                "What --bars is relative to, parent or root",
+                "bar-scale",
+            )
+            .with_value_name("SCALE")
+            .with_default_value("parent"),
+        )
+        .with_arg(
+            Arg::long_only(
+                "width",
+                "Fit lines to N columns instead of the width of the terminal",
+                "width",
+            )
+            .with_value_name("N"),
+        )
+        .with_arg(
+            Arg::long_only(
+                "theme",
+                "Color theme, dark, light, solarized or monochrome",
+                "theme",
+            )
+            .with_value_name("NAME"),
+        )
+        .with_arg(Arg::long_only(
+            "icons",
+            "Show an icon for the type of each entry",
+            "icons",
+        ))
+        .with_arg(
+            Arg::long_only(
+                "icon set",
+                "Icons shown by --icons, nerd or unicode for fonts without Nerd Font icons",
+                "icon-set",
+            )
+            .with_value_name("SET"),
+        )
+}
+
+/// Add the arguments for what is shown of each entry that browse can also toggle while it runs
+fn with_toggle_args(command: Command) -> Command {
+    command
+        .with_arg(Arg::new(
+            "no extentions",
+            "Disable file extentions",
+            "",
+            'x',
+        ))
+        .with_arg(Arg::new("file sizes", "Show file sizes", "", 'f'))
+        .with_arg(Arg::new("directory sizes", "Show directory sizes", "", 'd'))
+        .with_arg(Arg::new(
+            "file count",
+            "Show the file count in a directory",
+            "",
+            'c',
+        ))
+}
+
+/// Add the arguments for commands whose tree can be reshaped before it is shown
+fn with_tree_args(command: Command) -> Command {
+    command
+        .with_arg(Arg::long_only(
+            "prune",
+            "Hide directories without any files",
+            "prune",
+        ))
+        .with_arg(Arg::long_only(
+            "compact",
+            "Merge chains of directories with a single subdirectory",
+            "compact",
+        ))
+}
+
+fn transform_tree(command: &Command, tree: &mut DirectoryObject) {
+    if command.has("prune") {
+        prune_empty_directories(tree);
+    }
+
+    if command.has("compact") {
+        compact_directories(tree);
+    }
+}
+
+/// Read an argument holding a size, exiting if it can not be parsed
+fn get_size_arg(command: &Command, arg_name: &str) -> Option<u64> {
+    let value = command.get_arg(arg_name).to_option()?;
+
+    match parse_size(&value) {
+        Some(size) => Some(size),
+        None => {
+            exit_with_error(
+                &format!(
+                    "{} must be a size like 512, 10K or 1.5MB",
+                    arg_name.replace(' ', "-")
+                ),
+                false,
+            );
+            None
+        }
+    }
+}
+
+/// Size units and precision from the global arguments, exiting if more than one unit is given
+fn get_size_format(cli: &Cli) -> SizeFormat {
+    let units: Vec<SizeUnits> = [
+        ("si", SizeUnits::Si),
+        ("iec", SizeUnits::Iec),
+        ("bytes", SizeUnits::Bytes),
+    ]
+    .into_iter()
+    .filter(|(arg_name, _)| cli.has_global_arg(arg_name))
+    .map(|(_, units)| units)
+    .collect();
+
+    if units.len() > 1 {
+        exit_with_error("only one of --si, --iec and --bytes can be used", false);
+    }
+
+    let precision =
+        cli.get_global_arg("precision")
+            .to_option()
+            .map_or(DEFAULT_PRECISION, |precision| {
+                match precision.parse::<usize>() {
+                    Ok(precision) if precision <= 10 => precision,
+                    _ => {
+                        exit_with_error("precision must be a whole number from 0 to 10", false);
+                        DEFAULT_PRECISION
+                    }
+                }
+            });
+
+    SizeFormat {
+        units: units.first().copied().unwrap_or(SizeUnits::Binary),
+        precision,
+    }
+}
+
+/// Read an argument holding an age or date, exiting if it can not be parsed
+fn get_time_reference_arg(command: &Command, arg_name: &str) -> Option<SystemTime> {
+    let value = command.get_arg(arg_name).to_option()?;
+
+    match parse_time_reference(&value) {
+        Ok(time) => Some(time),
+        Err(e) => {
+            exit_with_error(&e, false);
+            None
+        }
+    }
+}
+
+/// Read the display arguments of a command, arguments a command does not take like the ones
+/// browse leaves out keep their defaults
+fn get_display_options(command: &Command, os: &OS) -> DisplayOptions {
+    let time_field = command
+        .get_arg("time field")
+        .to_option()
+        .map_or(Some(TimeField::Modified), |name| {
+            TimeField::from_string(&name)
+        })
+        .unwrap_or_else(|| {
+            exit_with_error(
+                "timefield must be \"modified\", \"accessed\", \"changed\" or \"created\"",
+                false,
+            );
+            TimeField::Modified
+        });
+
+    let max_entries =
+        command
+            .get_arg("max entries")
+            .to_option()
+            .map(|max_entries| match max_entries.parse::<usize>() {
+                Ok(max_entries) if max_entries > 0 => max_entries,
+                _ => {
+                    exit_with_error("max-entries must be a positive whole number", false);
+                    0
+                }
+            });
+
+    let bar_scale = command
+        .get_arg("bar scale")
+        .to_option()
+        .map_or(Some(BarScale::Parent), |name| BarScale::from_string(&name))
+        .unwrap_or_else(|| {
+            exit_with_error("bar-scale must be \"parent\" or \"root\"", false);
+            BarScale::Parent
+        });
+
+    let width = command
+        .get_arg("width")
+        .to_option()
+        .map(|width| match width.parse::<usize>() {
+            Ok(width) if width > 0 => width,
+            _ => {
+                exit_with_error("width must be a positive whole number", false);
+                0
+            }
+        });
+
+    // The theme is loaded even without colors so a mistake in it is always reported
+    let colors = match Theme::load(command.get_arg("theme").to_option(), os) {
+        Ok(theme) => Some(theme).filter(|_| colors_enabled()),
+        Err(e) => {
+            exit_with_error(&e, false);
+            None
+        }
+    };
+
+    let icons = match Icons::load(command.get_arg("icon set").to_option(), os) {
+        Ok(icons) => Some(icons).filter(|_| command.has("icons")),
+        Err(e) => {
+            exit_with_error(&e, false);
+            None
+        }
+    };
+
+    DisplayOptions {
+        show_endings: !command.has("no extentions"),
+        show_file_sizes: command.has("file sizes"),
+        show_directory_sizes: command.has("directory sizes"),
+        show_file_counts_in_directories: command.has("file count"),
+        show_line_counts: command.has("lines"),
+        show_permissions: command.has("permissions"),
+        show_owner: command.has("owner"),
+        show_group: command.has("group"),
+        show_date: command.has("date"),
+        time_field,
+        time_format: command
+            .get_arg("time format")
+            .to_option()
+            .unwrap_or(DEFAULT_TIME_FORMAT.to_string()),
+        max_entries,
+        path_mode: if command.has("absolute") {
+            PathMode::Absolute
+        } else if command.has("full path") || command.has("list") {
+            PathMode::Relative
+        } else {
+            PathMode::Name
+        },
+        list: command.has("list"),
+        size_bars: Some(bar_scale).filter(|_| command.has("bars")),
+        width,
+        colors,
+        icons,
+        highlighted_paths: HashSet::new(),
+        annotations: HashMap::new(),
+    }
+}
@@ -0,0 +1,548 @@
+use std::collections::HashMap;
+use std::fs::Metadata;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+pub const DEFAULT_TIME_FORMAT: &str = "%Y-%m-%d %H:%M";
+
+const MONTH_NAMES: [&str; 12] = [
+    "Jan", "Feb", "Mar", "Apr", "May", "Jun", "Jul", "Aug", "Sep", "Oct", "Nov", "Dec",
+];
+const WEEKDAY_NAMES: [&str; 7] = ["Sun", "Mon", "Tue", "Wed", "Thu", "Fri", "Sat"];
+
+#[derive(Debug, Clone, Default)]
+pub struct EntryMetadata {
+    pub modified: Option<SystemTime>,
+    pub accessed: Option<SystemTime>,
+    pub created: Option<SystemTime>,
+    /// Time of the last status change, only available on Unix
+    pub changed: Option<SystemTime>,
+    /// Unix file type and permission bits
+    pub mode: Option<u32>,
+    pub uid: Option<u32>,
+    pub gid: Option<u32>,
+    /// Windows file attribute flags
+    pub attributes: Option<u32>,
+    /// Device and inode number, shared by hard links of the same file, only available on Unix
+    pub file_id: Option<(u64, u64)>,
+    pub is_directory: bool,
+}
+
+impl EntryMetadata {
+    pub fn from_metadata(metadata: &Metadata) -> EntryMetadata {
+        let mut entry_metadata = EntryMetadata {
+            modified: metadata.modified().ok(),
+            accessed: metadata.accessed().ok(),
+            created: metadata.created().ok(),
+            is_directory: metadata.is_dir(),
+            ..EntryMetadata::default()
+        };
+
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::MetadataExt;
+
+            entry_metadata.mode = Some(metadata.mode());
+            entry_metadata.uid = Some(metadata.uid());
+            entry_metadata.gid = Some(metadata.gid());
+            entry_metadata.file_id = Some((metadata.dev(), metadata.ino()));
+            entry_metadata.changed = u64::try_from(metadata.ctime())
+                .ok()
+                .map(|seconds| UNIX_EPOCH + Duration::from_secs(seconds));
+        }
+
+        #[cfg(windows)]
+        {
+            use std::os::windows::fs::MetadataExt;
+
+            entry_metadata.attributes = Some(metadata.file_attributes());
+        }
+
+        entry_metadata
+    }
+
+    pub fn get_time(&self, time_field: TimeField) -> Option<SystemTime> {
+        match time_field {
+            TimeField::Modified => self.modified,
+            TimeField::Accessed => self.accessed,
+            TimeField::Created => self.created,
+            TimeField::Changed => self.changed,
+        }
+    }
+
+    /// Permissions in the style of ls, or the Windows attribute flags when there are no permissions
+    pub fn permissions_string(&self) -> String {
+        if let Some(mode) = self.mode {
+            let file_type = match mode & 0o170000 {
+                0o040000 => 'd',
+                0o120000 => 'l',
+                0o010000 => 'p',
+                0o140000 => 's',
+                0o020000 => 'c',
+                0o060000 => 'b',
+                _ => '-',
+            };
+
+            let mut permissions = String::from(file_type);
+            for shift in [6, 3, 0] {
+                let bits = (mode >> shift) & 0o7;
+                permissions.push(if bits & 0o4 != 0 { 'r' } else { '-' });
+                permissions.push(if bits & 0o2 != 0 { 'w' } else { '-' });
+                permissions.push(if bits & 0o1 != 0 { 'x' } else { '-' });
+            }
+            return permissions;
+        }
+
+        if let Some(attributes) = self.attributes {
+            // Attribute flags from the Windows API, in the order used by dir /a
+            let flags = [
+                (0x10, 'd'),
+                (0x20, 'a'),
+                (0x01, 'r'),
+                (0x02, 'h'),
+                (0x04, 's'),
+                (0x400, 'l'),
+            ];
+            return flags
+                .iter()
+                .map(
+                    |(flag, letter)| {
+                        if attributes & flag != 0 {
+                            *letter
+                        } else {
+                            '-'
+                        }
+                    },
+                )
+                .collect();
+        }
+
+        if self.is_directory {
+            "d---------".to_string()
+        } else {
+            "----------".to_string()
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum TimeField {
+    Modified,
+    Accessed,
+    Created,
+    Changed,
+}
+
+impl TimeField {
+    pub fn from_string(string: &str) -> Option<TimeField> {
+        match string {
+            "modified" | "mtime" => Some(TimeField::Modified),
+            "accessed" | "atime" => Some(TimeField::Accessed),
+            "created" | "birth" => Some(TimeField::Created),
+            "changed" | "ctime" => Some(TimeField::Changed),
+            _ => None,
+        }
+    }
+}
+
+struct DateTime {
+    year: i64,
+    month: u32,
+    day: u32,
+    hour: u32,
+    minute: u32,
+    second: u32,
+    weekday: u32,
+}
+
+/// Format a time using strftime style specifiers, %Y %y %m %d %e %H %M %S %b %a %s and %%
+pub fn format_time(time: SystemTime, format: &str) -> String {
+    let timestamp = match time.duration_since(UNIX_EPOCH) {
+        Ok(duration) => duration.as_secs() as i64,
+        Err(e) => -(e.duration().as_secs() as i64),
+    };
+    let date_time = local_date_time(timestamp);
+
+    let mut output = String::new();
+    let mut chars = format.chars();
+    while let Some(c) = chars.next() {
+        if c != '%' {
+            output.push(c);
+            continue;
+        }
+
+        match chars.next() {
+            Some('Y') => output.push_str(&date_time.year.to_string()),
+            Some('y') => output.push_str(&format!("{:02}", date_time.year.rem_euclid(100))),
+            Some('m') => output.push_str(&format!("{:02}", date_time.month)),
+            Some('d') => output.push_str(&format!("{:02}", date_time.day)),
+            Some('e') => output.push_str(&format!("{:>2}", date_time.day)),
+            Some('H') => output.push_str(&format!("{:02}", date_time.hour)),
+            Some('M') => output.push_str(&format!("{:02}", date_time.minute)),
+            Some('S') => output.push_str(&format!("{:02}", date_time.second)),
+            Some('b') => output.push_str(MONTH_NAMES[date_time.month as usize - 1]),
+            Some('a') => output.push_str(WEEKDAY_NAMES[date_time.weekday as usize]),
+            Some('s') => output.push_str(&timestamp.to_string()),
+            Some('%') => output.push('%'),
+            Some(other) => {
+                output.push('%');
+                output.push(other);
+            }
+            None => output.push('%'),
+        }
+    }
+
+    output
+}
+
+/// Parse a point in time given either as an age relative to now, like 30m, 2h or 1d12h,
+/// or as a local date in the form YYYY-MM-DD with an optional HH:MM or HH:MM:SS time
+pub fn parse_time_reference(value: &str) -> Result<SystemTime, String> {
+    if let Some(age) = parse_age(value) {
+        return SystemTime::now()
+            .checked_sub(age)
+            .ok_or(format!("{} is too far in the past", value));
+    }
+
+    let timestamp = parse_local_date(value).ok_or(format!(
+        "{} is not an age like 2h or 3d or a date like 2024-01-31 12:00",
+        value
+    ))?;
+
+    Ok(if timestamp >= 0 {
+        UNIX_EPOCH + Duration::from_secs(timestamp as u64)
+    } else {
+        UNIX_EPOCH - Duration::from_secs(timestamp.unsigned_abs())
+    })
+}
+
+/// Parse an age made of numbers followed by a unit, s, m, h, d or w
+fn parse_age(value: &str) -> Option<Duration> {
+    let mut seconds: u64 = 0;
+    let mut number = String::new();
+
+    for c in value.chars() {
+        if c.is_ascii_digit() {
+            number.push(c);
+            continue;
+        }
+
+        let unit_seconds = match c {
+            's' => 1,
+            'm' => 60,
+            'h' => 60 * 60,
+            'd' => 24 * 60 * 60,
+            'w' => 7 * 24 * 60 * 60,
+            _ => return None,
+        };
+        let amount: u64 = number.parse().ok()?;
+        seconds = seconds.checked_add(amount.checked_mul(unit_seconds)?)?;
+        number.clear();
+    }
+
+    // Every number needs a unit, a bare number could be a year
+    if !number.is_empty() || value.is_empty() {
+        return None;
+    }
+
+    Some(Duration::from_secs(seconds))
+}
+
+fn parse_local_date(value: &str) -> Option<i64> {
+    let (date, time) = match value.split_once([' ', 'T']) {
+        Some((date, time)) => (date, Some(time)),
+        None => (value, None),
+    };
+
+    let date_parts: Vec<&str> = date.split('-').collect();
+    if date_parts.len() != 3 {
+        return None;
+    }
+    let year: i64 = date_parts[0].parse().ok()?;
+    let month: i64 = date_parts[1].parse().ok()?;
+    let day: i64 = date_parts[2].parse().ok()?;
+    if !(1..=12).contains(&month) || !(1..=days_in_month(year, month)).contains(&day) {
+        return None;
+    }
+
+    let mut seconds_of_day = 0;
+    if let Some(time) = time {
+        let time_parts: Vec<&str> = time.split(':').collect();
+        if time_parts.len() < 2 || time_parts.len() > 3 {
+            return None;
+        }
+        let mut multiplier = 3600;
+        for part in time_parts {
+            let amount: i64 = part.parse().ok()?;
+            if amount < 0 || amount >= if multiplier == 3600 { 24 } else { 60 } {
+                return None;
+            }
+            seconds_of_day += amount * multiplier;
+            multiplier /= 60;
+        }
+    }
+
+    let utc_timestamp = days_from_civil(year, month, day) * 86400 + seconds_of_day;
+
+    // Shift by the offset of the local time zone at that moment
+    let local = local_date_time(utc_timestamp);
+    let local_as_utc = days_from_civil(local.year, local.month as i64, local.day as i64) * 86400
+        + local.hour as i64 * 3600
+        + local.minute as i64 * 60
+        + local.second as i64;
+
+    Some(utc_timestamp - (local_as_utc - utc_timestamp))
+}
+
+/// Days since 1970-01-01, see http://howardhinnant.github.io/date_algorithms.html
+fn days_from_civil(year: i64, month: i64, day: i64) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let year_of_era = year.rem_euclid(400);
+    let month_index = if month > 2 { month - 3 } else { month + 9 };
+    let day_of_year = (153 * month_index + 2) / 5 + day - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+
+    era * 146097 + day_of_era - 719468
+}
+
+fn days_in_month(year: i64, month: i64) -> i64 {
+    match month {
+        2 if year % 4 == 0 && (year % 100 != 0 || year % 400 == 0) => 29,
+        2 => 28,
+        4 | 6 | 9 | 11 => 30,
+        _ => 31,
+    }
+}
+
+/// Convert a unix timestamp to a date in the local time zone
+#[cfg(unix)]
+fn local_date_time(timestamp: i64) -> DateTime {
+    use std::ffi::{c_char, c_int, c_long};
+
+    // Layout shared by glibc, musl and macOS
+    #[repr(C)]
+    struct Tm {
+        tm_sec: c_int,
+        tm_min: c_int,
+        tm_hour: c_int,
+        tm_mday: c_int,
+        tm_mon: c_int,
+        tm_year: c_int,
+        tm_wday: c_int,
+        tm_yday: c_int,
+        tm_isdst: c_int,
+        tm_gmtoff: c_long,
+        tm_zone: *const c_char,
+    }
+
+    // time_t is a long on most systems, while musl, NetBSD and OpenBSD made it 64 bits wide
+    // everywhere
+    #[cfg(not(any(target_env = "musl", target_os = "netbsd", target_os = "openbsd")))]
+    type TimeT = c_long;
+    #[cfg(any(target_env = "musl", target_os = "netbsd", target_os = "openbsd"))]
+    type TimeT = i64;
+
+    extern "C" {
+        fn localtime_r(time: *const TimeT, result: *mut Tm) -> *mut Tm;
+    }
+
+    // Times past 2038 do not fit a 32 bit time_t
+    let time = match TimeT::try_from(timestamp) {
+        Ok(time) => time,
+        Err(_) => return utc_date_time(timestamp),
+    };
+
+    let mut tm = Tm {
+        tm_sec: 0,
+        tm_min: 0,
+        tm_hour: 0,
+        tm_mday: 0,
+        tm_mon: 0,
+        tm_year: 0,
+        tm_wday: 0,
+        tm_yday: 0,
+        tm_isdst: 0,
+        tm_gmtoff: 0,
+        tm_zone: std::ptr::null(),
+    };
+
+    let result = unsafe { localtime_r(&time, &mut tm) };
+    if result.is_null() {
+        return utc_date_time(timestamp);
+    }
+
+    DateTime {
+        year: tm.tm_year as i64 + 1900,
+        month: tm.tm_mon as u32 + 1,
+        day: tm.tm_mday as u32,
+        hour: tm.tm_hour as u32,
+        minute: tm.tm_min as u32,
+        second: tm.tm_sec as u32,
+        weekday: tm.tm_wday as u32,
+    }
+}
+
+/// Times are shown in UTC where the local time zone can not be looked up
+#[cfg(not(unix))]
+fn local_date_time(timestamp: i64) -> DateTime {
+    utc_date_time(timestamp)
+}
+
+fn utc_date_time(timestamp: i64) -> DateTime {
+    let days = timestamp.div_euclid(86400);
+    let seconds_of_day = timestamp.rem_euclid(86400);
+
+    // Civil from days, see http://howardhinnant.github.io/date_algorithms.html
+    let z = days + 719468;
+    let era = z.div_euclid(146097);
+    let day_of_era = z.rem_euclid(146097);
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_index = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * month_index + 2) / 5 + 1;
+    let month = if month_index < 10 {
+        month_index + 3
+    } else {
+        month_index - 9
+    };
+    let year = year_of_era + era * 400 + if month <= 2 { 1 } else { 0 };
+
+    DateTime {
+        year,
+        month: month as u32,
+        day: day as u32,
+        hour: (seconds_of_day / 3600) as u32,
+        minute: (seconds_of_day % 3600 / 60) as u32,
+        second: (seconds_of_day % 60) as u32,
+        // 1970-01-01 was a Thursday
+        weekday: (days + 4).rem_euclid(7) as u32,
+    }
+}
+
+/// Looks up user and group names, remembering names that were already found
+#[derive(Default)]
+pub struct OwnerNames {
+    users: HashMap<u32, String>,
+    groups: HashMap<u32, String>,
+}
+
+impl OwnerNames {
+    pub fn user_name(&mut self, uid: u32) -> String {
+        self.users
+            .entry(uid)
+            .or_insert_with(|| lookup_user_name(uid).unwrap_or(uid.to_string()))
+            .clone()
+    }
+
+    pub fn group_name(&mut self, gid: u32) -> String {
+        self.groups
+            .entry(gid)
+            .or_insert_with(|| lookup_group_name(gid).unwrap_or(gid.to_string()))
+            .clone()
+    }
+}
+
+#[cfg(unix)]
+extern "C" {
+    fn getpwuid(uid: u32) -> *const *const std::ffi::c_char;
+    fn getgrgid(gid: u32) -> *const *const std::ffi::c_char;
+}
+
+/// The name is the first field of both passwd and group on every Unix, so only that is read
+#[cfg(unix)]
+fn read_first_name_field(entry: *const *const std::ffi::c_char) -> Option<String> {
+    if entry.is_null() {
+        return None;
+    }
+
+    unsafe {
+        let name = *entry;
+        if name.is_null() {
+            return None;
+        }
+        Some(std::ffi::CStr::from_ptr(name).to_string_lossy().to_string())
+    }
+}
+
+#[cfg(unix)]
+fn lookup_user_name(uid: u32) -> Option<String> {
+    read_first_name_field(unsafe { getpwuid(uid) })
+}
+
+#[cfg(unix)]
+fn lookup_group_name(gid: u32) -> Option<String> {
+    read_first_name_field(unsafe { getgrgid(gid) })
+}
+
+#[cfg(not(unix))]
+fn lookup_user_name(_uid: u32) -> Option<String> {
+    None
+}
+
+#[cfg(not(unix))]
+fn lookup_group_name(_gid: u32) -> Option<String> {
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{parse_age, parse_local_date, parse_time_reference};
+    use std::time::{Duration, SystemTime};
+
+    #[test]
+    fn parses_ages() {
+        assert_eq!(parse_age("30s"), Some(Duration::from_secs(30)));
+        assert_eq!(parse_age("2h"), Some(Duration::from_secs(2 * 3600)));
+        assert_eq!(parse_age("1d12h"), Some(Duration::from_secs(36 * 3600)));
+        assert_eq!(parse_age("1w"), Some(Duration::from_secs(7 * 86400)));
+    }
+
+    #[test]
+    fn rejects_malformed_ages() {
+        for value in ["", "2", "h", "2x", "1d2", "-1h", "99999999999999999999w"] {
+            assert_eq!(parse_age(value), None, "{}", value);
+        }
+    }
+
+    #[test]
+    fn parses_dates_and_times() {
+        let day = parse_local_date("2024-01-01").unwrap();
+        assert_eq!(parse_local_date("2024-01-01T00:00"), Some(day));
+        assert_eq!(parse_local_date("2024-01-01 12:30"), Some(day + 45000));
+        assert_eq!(parse_local_date("2024-01-01 12:30:15"), Some(day + 45015));
+        assert!(parse_local_date("2024-02-29").is_some());
+        assert!(parse_local_date("2000-02-29").is_some());
+        assert!(parse_local_date("2024-12-31 23:59:59").is_some());
+    }
+
+    #[test]
+    fn rejects_impossible_dates() {
+        for value in [
+            "2024-02-30",
+            "2023-02-29",
+            "1900-02-29",
+            "2024-04-31",
+            "2024-13-01",
+            "2024-00-10",
+            "2024-01-00",
+            "2024-01-01 24:00",
+            "2024-01-01 12:60",
+            "2024-01-01 12",
+            "2024-01",
+            "yesterday",
+        ] {
+            assert_eq!(parse_local_date(value), None, "{}", value);
+        }
+    }
+
+    #[test]
+    fn time_references_are_in_the_past() {
+        let before = SystemTime::now();
+        let reference = parse_time_reference("1h").unwrap();
+        let age = before.duration_since(reference).unwrap();
+        assert!(age >= Duration::from_secs(3599) && age <= Duration::from_secs(3601));
+        assert!(parse_time_reference("2024-01-31 12:00").is_ok());
+        assert!(parse_time_reference("2024-01-32").is_err());
+    }
+}
@@ -0,0 +1,641 @@
+use std::collections::HashSet;
+
+/// A small backtracking regular expression engine supporting literals, `.`, character classes
+/// like `[a-z]` and `[^0-9]`, the escapes `\d \w \s \D \W \S \b \B`, anchors `^` and `$`,
+/// groups with alternation `(a|b)` and the quantifiers `* + ? {n} {n,} {n,m}`, each with a
+/// lazy `?` variant
+#[derive(Debug)]
+pub struct Regex {
+    alternatives: Vec<Vec<Node>>,
+    ignore_case: bool,
+}
+
+#[derive(Debug)]
+enum Node {
+    Char(char),
+    Any,
+    Class(Class),
+    Start,
+    End,
+    WordBoundary(bool),
+    Group(Vec<Vec<Node>>),
+    Repeat {
+        node: Box<Node>,
+        min: usize,
+        max: Option<usize>,
+        greedy: bool,
+    },
+}
+
+#[derive(Debug)]
+struct Class {
+    items: Vec<ClassItem>,
+    negated: bool,
+}
+
+#[derive(Debug)]
+enum ClassItem {
+    Range(char, char),
+    Digit(bool),
+    Word(bool),
+    Space(bool),
+}
+
+impl ClassItem {
+    fn matches(&self, c: char) -> bool {
+        match self {
+            ClassItem::Range(start, end) => *start <= c && c <= *end,
+            ClassItem::Digit(negated) => c.is_ascii_digit() != *negated,
+            ClassItem::Word(negated) => is_word_char(c) != *negated,
+            ClassItem::Space(negated) => c.is_whitespace() != *negated,
+        }
+    }
+}
+
+impl Class {
+    fn matches(&self, c: char, ignore_case: bool) -> bool {
+        let found = self.items.iter().any(|item| {
+            item.matches(c)
+                || (ignore_case
+                    && (c.to_lowercase().any(|lower| item.matches(lower))
+                        || c.to_uppercase().any(|upper| item.matches(upper))))
+        });
+        found != self.negated
+    }
+}
+
+fn is_word_char(c: char) -> bool {
+    c.is_alphanumeric() || c == '_'
+}
+
+impl Regex {
+    pub fn new(pattern: &str, ignore_case: bool) -> Result<Regex, String> {
+        let mut parser = Parser {
+            chars: pattern.chars().collect(),
+            position: 0,
+        };
+
+        let alternatives = parser.parse_alternatives()?;
+        if parser.position < parser.chars.len() {
+            return Err(format!(
+                "unmatched ) at position {} in pattern {}",
+                parser.position + 1,
+                pattern
+            ));
+        }
+
+        Ok(Regex {
+            alternatives,
+            ignore_case,
+        })
+    }
+
+    /// Byte ranges of every non overlapping match in the text
+    pub fn find_all(&self, text: &str) -> Vec<(usize, usize)> {
+        let offsets: Vec<usize> = text.char_indices().map(|(offset, _)| offset).collect();
+        let chars: Vec<char> = text.chars().collect();
+        let byte_offset = |index: usize| offsets.get(index).copied().unwrap_or(text.len());
+
+        let matcher = Matcher {
+            text: &chars,
+            ignore_case: self.ignore_case,
+        };
+
+        let mut matches = Vec::new();
+        let mut index = 0;
+
+        while index <= chars.len() {
+            let mut end = None;
+            matcher.match_alternatives(&self.alternatives, index, &mut |position| {
+                end = Some(position);
+                true
+            });
+
+            match end {
+                Some(end) => {
+                    matches.push((byte_offset(index), byte_offset(end)));
+                    // Step over empty matches so the search always moves forward
+                    index = if end > index { end } else { index + 1 };
+                }
+                None => index += 1,
+            }
+        }
+
+        matches
+    }
+}
+
+struct Parser {
+    chars: Vec<char>,
+    position: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.position).copied()
+    }
+
+    fn next(&mut self) -> Option<char> {
+        let c = self.peek();
+        self.position += 1;
+        c
+    }
+
+    /// Move past the current character, returning the value given
+    fn skip<T>(&mut self, value: T) -> T {
+        self.position += 1;
+        value
+    }
+
+    fn parse_alternatives(&mut self) -> Result<Vec<Vec<Node>>, String> {
+        let mut alternatives = vec![self.parse_sequence()?];
+        while self.peek() == Some('|') {
+            self.position += 1;
+            alternatives.push(self.parse_sequence()?);
+        }
+        Ok(alternatives)
+    }
+
+    fn parse_sequence(&mut self) -> Result<Vec<Node>, String> {
+        let mut sequence = Vec::new();
+
+        while let Some(c) = self.peek() {
+            if c == '|' || c == ')' {
+                break;
+            }
+
+            let node = self.parse_atom()?;
+            let node = self.parse_quantifier(node)?;
+            sequence.push(node);
+        }
+
+        Ok(sequence)
+    }
+
+    fn parse_atom(&mut self) -> Result<Node, String> {
+        let c = self.next().unwrap_or_default();
+        Ok(match c {
+            '.' => Node::Any,
+            '^' => Node::Start,
+            '$' => Node::End,
+            '(' => {
+                // Non capturing groups behave the same since nothing is captured
+                if self.chars[self.position..].starts_with(&['?', ':']) {
+                    self.position += 2;
+                }
+                let alternatives = self.parse_alternatives()?;
+                if self.next() != Some(')') {
+                    return Err("missing ) in pattern".to_string());
+                }
+                Node::Group(alternatives)
+            }
+            '[' => Node::Class(self.parse_class()?),
+            '\\' => self.parse_escape()?,
+            '*' | '+' | '?' => return Err(format!("nothing to repeat before {}", c)),
+            c => Node::Char(c),
+        })
+    }
+
+    fn parse_escape(&mut self) -> Result<Node, String> {
+        let c = self
+            .next()
+            .ok_or("pattern can not end with \\".to_string())?;
+
+        Ok(match c {
+            'b' => Node::WordBoundary(true),
+            'B' => Node::WordBoundary(false),
+            _ => match escape_class_item(c) {
+                Some(item) => Node::Class(Class {
+                    items: vec![item],
+                    negated: false,
+                }),
+                None => Node::Char(escaped_char(c)),
+            },
+        })
+    }
+
+    fn parse_class(&mut self) -> Result<Class, String> {
+        let negated = self.peek() == Some('^');
+        if negated {
+            self.position += 1;
+        }
+
+        let mut items = Vec::new();
+        let mut first = true;
+
+        loop {
+            let c = self.next().ok_or("missing ] in pattern".to_string())?;
+            if c == ']' && !first {
+                break;
+            }
+            first = false;
+
+            let start = if c == '\\' {
+                let escaped = self.next().ok_or("missing ] in pattern".to_string())?;
+                if let Some(item) = escape_class_item(escaped) {
+                    items.push(item);
+                    continue;
+                }
+                escaped_char(escaped)
+            } else {
+                c
+            };
+
+            let is_range = self.peek() == Some('-')
+                && self
+                    .chars
+                    .get(self.position + 1)
+                    .is_some_and(|&end| end != ']');
+            if is_range {
+                self.position += 1;
+                let mut end = self.next().unwrap_or_default();
+                if end == '\\' {
+                    end = escaped_char(self.next().unwrap_or_default());
+                }
+                if end < start {
+                    return Err(format!("invalid range {}-{} in pattern", start, end));
+                }
+                items.push(ClassItem::Range(start, end));
+            } else {
+                items.push(ClassItem::Range(start, start));
+            }
+        }
+
+        Ok(Class { items, negated })
+    }
+
+    fn parse_quantifier(&mut self, node: Node) -> Result<Node, String> {
+        let (min, max) = match self.peek() {
+            Some('*') => self.skip((0, None)),
+            Some('+') => self.skip((1, None)),
+            Some('?') => self.skip((0, Some(1))),
+            Some('{') => match self.parse_counted_repeat() {
+                Some(bounds) => bounds,
+                // A brace that does not start a valid repeat is a literal
+                None => return Ok(node),
+            },
+            _ => return Ok(node),
+        };
+
+        if matches!(node, Node::Start | Node::End | Node::WordBoundary(_)) {
+            return Err("anchors can not be repeated".to_string());
+        }
+
+        let greedy = self.peek() != Some('?');
+        if !greedy {
+            self.position += 1;
+        }
+
+        Ok(Node::Repeat {
+            node: Box::new(node),
+            min,
+            max,
+            greedy,
+        })
+    }
+
+    /// Parse `{n}`, `{n,}` or `{n,m}`, leaving the position after the closing brace
+    fn parse_counted_repeat(&mut self) -> Option<(usize, Option<usize>)> {
+        let close = self.chars[self.position..].iter().position(|&c| c == '}')?;
+        let inner: String = self.chars[self.position + 1..self.position + close]
+            .iter()
+            .collect();
+
+        let bounds = match inner.split_once(',') {
+            None => {
+                let count = inner.parse().ok()?;
+                (count, Some(count))
+            }
+            Some((min, "")) => (min.parse().ok()?, None),
+            Some((min, max)) => {
+                let min = min.parse().ok()?;
+                let max = max.parse().ok()?;
+                if max < min {
+                    return None;
+                }
+                (min, Some(max))
+            }
+        };
+
+        self.position += close + 1;
+        Some(bounds)
+    }
+}
+
+fn escape_class_item(c: char) -> Option<ClassItem> {
+    match c {
+        'd' => Some(ClassItem::Digit(false)),
+        'D' => Some(ClassItem::Digit(true)),
+        'w' => Some(ClassItem::Word(false)),
+        'W' => Some(ClassItem::Word(true)),
+        's' => Some(ClassItem::Space(false)),
+        'S' => Some(ClassItem::Space(true)),
+        _ => None,
+    }
+}
+
+fn escaped_char(c: char) -> char {
+    match c {
+        'n' => '\n',
+        't' => '\t',
+        'r' => '\r',
+        c => c,
+    }
+}
+
+struct Matcher<'a> {
+    text: &'a [char],
+    ignore_case: bool,
+}
+
+impl Matcher<'_> {
+    fn match_alternatives(
+        &self,
+        alternatives: &[Vec<Node>],
+        position: usize,
+        next: &mut dyn FnMut(usize) -> bool,
+    ) -> bool {
+        alternatives
+            .iter()
+            .any(|sequence| self.match_sequence(sequence, position, next))
+    }
+
+    fn match_sequence(
+        &self,
+        sequence: &[Node],
+        position: usize,
+        next: &mut dyn FnMut(usize) -> bool,
+    ) -> bool {
+        match sequence.split_first() {
+            None => next(position),
+            Some((node, rest)) => self.match_node(node, position, &mut |after| {
+                self.match_sequence(rest, after, next)
+            }),
+        }
+    }
+
+    fn match_node(
+        &self,
+        node: &Node,
+        position: usize,
+        next: &mut dyn FnMut(usize) -> bool,
+    ) -> bool {
+        match node {
+            Node::Start => position == 0 && next(position),
+            Node::End => position == self.text.len() && next(position),
+            Node::WordBoundary(expected) => {
+                let before = position > 0 && is_word_char(self.text[position - 1]);
+                let after = position < self.text.len() && is_word_char(self.text[position]);
+                (before != after) == *expected && next(position)
+            }
+            Node::Group(alternatives) => self.match_alternatives(alternatives, position, next),
+            Node::Repeat { node: inner, .. } => {
+                if self.is_single_char(inner) {
+                    self.match_single_char_repeat(node, position, next)
+                } else {
+                    self.match_repeat(node, position, next)
+                }
+            }
+            _ => self.matches_char(node, position) && next(position + 1),
+        }
+    }
+
+    fn is_single_char(&self, node: &Node) -> bool {
+        matches!(node, Node::Char(_) | Node::Any | Node::Class(_))
+    }
+
+    fn matches_char(&self, node: &Node, position: usize) -> bool {
+        let c = match self.text.get(position) {
+            Some(c) => *c,
+            None => return false,
+        };
+
+        match node {
+            Node::Char(expected) => {
+                c == *expected || (self.ignore_case && c.to_lowercase().eq(expected.to_lowercase()))
+            }
+            Node::Any => c != '\n',
+            Node::Class(class) => class.matches(c, self.ignore_case),
+            _ => false,
+        }
+    }
+
+    /// Repeats of a single character are matched in a loop rather than by recursion, so long
+    /// lines matched by patterns like `.*` do not use a stack frame per character
+    fn match_single_char_repeat(
+        &self,
+        repeat: &Node,
+        position: usize,
+        next: &mut dyn FnMut(usize) -> bool,
+    ) -> bool {
+        let Node::Repeat {
+            node,
+            min,
+            max,
+            greedy,
+        } = repeat
+        else {
+            return false;
+        };
+        let (min, max, greedy) = (*min, *max, *greedy);
+
+        let mut count = 0;
+        while max.is_none_or(|max| count < max) && self.matches_char(node, position + count) {
+            count += 1;
+        }
+
+        if count < min {
+            return false;
+        }
+
+        if greedy {
+            (min..=count).rev().any(|length| next(position + length))
+        } else {
+            (min..=count).any(|length| next(position + length))
+        }
+    }
+
+    /// Repeats of anything longer than a single character keep their own stack of where each
+    /// repetition could end, so a group repeated once per character of a long line does not
+    /// use a stack frame per repetition
+    fn match_repeat(
+        &self,
+        repeat: &Node,
+        position: usize,
+        next: &mut dyn FnMut(usize) -> bool,
+    ) -> bool {
+        let Node::Repeat {
+            node,
+            min,
+            max,
+            greedy,
+        } = repeat
+        else {
+            return false;
+        };
+        let (min, max, greedy) = (*min, *max, *greedy);
+
+        // Each repetition is (count so far, position, ends of one more repetition left to try)
+        let mut stack = vec![(
+            0,
+            position,
+            self.repetition_ends(node, 0, min, max, position),
+        )];
+        if !greedy && min == 0 && next(position) {
+            return true;
+        }
+
+        // Once the minimum is reached an unbounded repeat continues the same way from a
+        // position whatever the count, so positions that failed once can be skipped
+        let mut failed = HashSet::new();
+        let skip = |count: usize, position: usize, failed: &HashSet<usize>| {
+            max.is_none() && count >= min && failed.contains(&position)
+        };
+
+        while let Some((count, position, ends)) = stack.last_mut() {
+            match ends.pop() {
+                Some(after) => {
+                    let count = *count + 1;
+                    if skip(count, after, &failed) {
+                        continue;
+                    }
+                    if !greedy && count >= min && next(after) {
+                        return true;
+                    }
+                    let ends = self.repetition_ends(node, count, min, max, after);
+                    stack.push((count, after, ends));
+                }
+                None => {
+                    if greedy && *count >= min && next(*position) {
+                        return true;
+                    }
+                    if *count >= min {
+                        failed.insert(*position);
+                    }
+                    stack.pop();
+                }
+            }
+        }
+
+        false
+    }
+
+    /// Positions one more repetition of a node can end at, the preferred one last
+    fn repetition_ends(
+        &self,
+        node: &Node,
+        count: usize,
+        min: usize,
+        max: Option<usize>,
+        position: usize,
+    ) -> Vec<usize> {
+        let mut ends = Vec::new();
+        if max.is_none_or(|max| count < max) {
+            self.match_node(node, position, &mut |after| {
+                // A repeat that matched nothing would loop forever
+                if !(after == position && count >= min) {
+                    ends.push(after);
+                }
+                false
+            });
+        }
+
+        if ends.len() > 1 {
+            let mut seen = HashSet::new();
+            ends.retain(|&end| seen.insert(end));
+        }
+        ends.reverse();
+        ends
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Regex;
+
+    fn find_all(pattern: &str, text: &str) -> Vec<(usize, usize)> {
+        Regex::new(pattern, false).unwrap().find_all(text)
+    }
+
+    #[test]
+    fn finds_literals_and_classes() {
+        assert_eq!(find_all("ab", "xabyab"), vec![(1, 3), (4, 6)]);
+        assert_eq!(find_all("a.c", "abc a-c ac"), vec![(0, 3), (4, 7)]);
+        assert_eq!(find_all("[0-9]+", "a12b3"), vec![(1, 3), (4, 5)]);
+        assert_eq!(find_all("[^a-z]", "ab1c"), vec![(2, 3)]);
+        assert_eq!(find_all(r"\d\s\w", "1 a"), vec![(0, 3)]);
+        assert!(find_all("xyz", "abc").is_empty());
+    }
+
+    #[test]
+    fn respects_anchors_and_boundaries() {
+        assert_eq!(find_all("^a", "aa"), vec![(0, 1)]);
+        assert_eq!(find_all("a$", "aa"), vec![(1, 2)]);
+        assert_eq!(
+            find_all(r"\bcat\b", "cat concat cat"),
+            vec![(0, 3), (11, 14)]
+        );
+        assert_eq!(find_all(r"\Bcat", "cat concat"), vec![(7, 10)]);
+    }
+
+    #[test]
+    fn repeats_greedily_and_lazily() {
+        assert_eq!(find_all("a+", "aaa"), vec![(0, 3)]);
+        assert_eq!(find_all("a+?", "aaa"), vec![(0, 1), (1, 2), (2, 3)]);
+        assert_eq!(find_all("<.*>", "<a><b>"), vec![(0, 6)]);
+        assert_eq!(find_all("<.*?>", "<a><b>"), vec![(0, 3), (3, 6)]);
+        assert_eq!(find_all("a{2}", "aaaaa"), vec![(0, 2), (2, 4)]);
+        assert_eq!(find_all("a{2,}", "aaaaa"), vec![(0, 5)]);
+        assert_eq!(find_all("a{1,2}", "aaa"), vec![(0, 2), (2, 3)]);
+        assert_eq!(find_all("ab?c", "ac abc"), vec![(0, 2), (3, 6)]);
+    }
+
+    #[test]
+    fn backtracks_through_groups() {
+        assert_eq!(find_all("(a|ab)c", "abc"), vec![(0, 3)]);
+        assert_eq!(find_all("(ab)+", "ababx"), vec![(0, 4)]);
+        assert_eq!(find_all("(a*)*b", "aaab"), vec![(0, 4)]);
+        assert_eq!(find_all("(a|b)*c", "abac"), vec![(0, 4)]);
+    }
+
+    #[test]
+    fn empty_matches_move_forward() {
+        assert_eq!(find_all("x*", "ab"), vec![(0, 0), (1, 1), (2, 2)]);
+    }
+
+    #[test]
+    fn ignores_case() {
+        let regex = Regex::new("hello", true).unwrap();
+        assert_eq!(regex.find_all("HeLLo"), vec![(0, 5)]);
+        let regex = Regex::new("[a-c]+", true).unwrap();
+        assert_eq!(regex.find_all("ABC"), vec![(0, 3)]);
+    }
+
+    #[test]
+    fn returns_byte_offsets() {
+        assert_eq!(find_all("b", "éb"), vec![(2, 3)]);
+    }
+
+    #[test]
+    fn rejects_malformed_patterns() {
+        for pattern in ["(a", "a)", "[a", "*a", r"\"] {
+            assert!(Regex::new(pattern, false).is_err(), "{}", pattern);
+        }
+    }
+
+    #[test]
+    fn treats_unfinished_braces_literally() {
+        assert_eq!(find_all("a{2", "aa{2"), vec![(1, 4)]);
+        assert_eq!(find_all("a{x}", "a{x}"), vec![(0, 4)]);
+    }
+
+    #[test]
+    fn handles_long_lines_without_overflowing() {
+        let text = "a".repeat(1_000_000);
+        assert_eq!(find_all(r"(\w|-)+", &text), vec![(0, text.len())]);
+        assert_eq!(
+            find_all("(a|b)*", &text),
+            vec![(0, text.len()), (text.len(), text.len())]
+        );
+    }
+}
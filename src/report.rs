@@ -0,0 +1,112 @@
+use crate::json::JsonValue;
+use crate::scan::DirectoryObject;
+use crate::size::bytes_to_best_size;
+use crate::utils::format_count;
+use std::time::Duration;
+
+/// Totals of a scan, shown below the tree like the "N directories, M files" line of tree
+pub struct ScanReport {
+    pub directories: u64,
+    pub files: u64,
+    pub bytes: u64,
+    pub ignored: u64,
+    pub errors: u64,
+    pub duration: Duration,
+}
+
+impl ScanReport {
+    pub fn from_tree(tree: &DirectoryObject, duration: Duration) -> ScanReport {
+        let mut report = ScanReport {
+            directories: 0,
+            files: 0,
+            bytes: 0,
+            ignored: 0,
+            errors: 0,
+            duration,
+        };
+        report.add_skipped_entries(tree);
+        report.count_entries(tree);
+        report
+    }
+
+    /// Recount the directories, files and bytes, used after the tree has been filtered so
+    /// the totals match what is shown
+    pub fn count_entries(&mut self, tree: &DirectoryObject) {
+        self.directories = 0;
+        self.files = 0;
+        self.bytes = 0;
+        self.add_entries(tree);
+
+        // The scanned directory itself is not counted
+        self.directories -= 1;
+    }
+
+    fn add_entries(&mut self, directory: &DirectoryObject) {
+        self.directories += 1;
+        self.files += directory.files.len() as u64;
+        self.bytes += directory.size;
+
+        for sub_directory in &directory.directories {
+            self.add_entries(sub_directory);
+        }
+    }
+
+    fn add_skipped_entries(&mut self, directory: &DirectoryObject) {
+        self.ignored += directory.ignored_count;
+        self.errors += directory.error_count;
+
+        for sub_directory in &directory.directories {
+            self.add_skipped_entries(sub_directory);
+        }
+    }
+}
+
+fn plural(count: u64, singular: &str, plural: &str) -> String {
+    format!(
+        "{} {}",
+        format_count(count),
+        if count == 1 { singular } else { plural }
+    )
+}
+
+pub fn display_report(report: &ScanReport) {
+    println!();
+    println!(
+        "{}, {}, {}, {} ignored, {}, scanned in {:.2}s",
+        plural(report.directories, "directory", "directories"),
+        plural(report.files, "file", "files"),
+        bytes_to_best_size(report.bytes),
+        format_count(report.ignored),
+        plural(report.errors, "error", "errors"),
+        report.duration.as_secs_f64()
+    );
+}
+
+pub fn report_to_json(report: &ScanReport) -> JsonValue {
+    JsonValue::object()
+        .with("directories", report.directories.into())
+        .with("files", report.files.into())
+        .with("bytes", report.bytes.into())
+        .with("ignored", report.ignored.into())
+        .with("errors", report.errors.into())
+        .with("duration_seconds", report.duration.as_secs_f64().into())
+}
+
+/// Read a report saved by `report_to_json`, none if any of its fields is missing
+pub fn report_from_json(value: &JsonValue) -> Option<ScanReport> {
+    let count = |key: &str| value.get(key).and_then(|count| count.as_u64());
+
+    Some(ScanReport {
+        directories: count("directories")?,
+        files: count("files")?,
+        bytes: count("bytes")?,
+        ignored: count("ignored")?,
+        errors: count("errors")?,
+        duration: Duration::from_secs_f64(
+            value
+                .get("duration_seconds")
+                .and_then(|seconds| seconds.as_f64())
+                .filter(|seconds| seconds.is_finite() && *seconds >= 0.0)?,
+        ),
+    })
+}
@@ -1,5 +1,9 @@
 use std::fs;
+use std::path::Path;
 
+use crate::loc::{CodeStats, LineCounts};
+use crate::metadata::EntryMetadata;
+use crate::size::bytes_to_best_size;
 use crate::system::config::{get_setting_from_config, ConfigOption};
 use crate::OS;
 
@@ -12,10 +16,16 @@ pub struct DirectoryObject {
     pub size: u64,
     pub size_string: String,
     pub file_count: u64,
+    pub line_counts: Option<LineCounts>,
+    pub metadata: Option<EntryMetadata>,
+    /// Entries directly in this directory skipped because of the ignore lists
+    pub ignored_count: u64,
+    /// Entries directly in this directory that could not be read
+    pub error_count: u64,
 }
 
 impl DirectoryObject {
-    fn new(name: &String, path: &String) -> DirectoryObject {
+    pub fn new(name: &String, path: &String) -> DirectoryObject {
         DirectoryObject {
             directories: Vec::new(),
             files: Vec::new(),
@@ -24,16 +34,51 @@ impl DirectoryObject {
             size: 0,
             size_string: String::new(),
             file_count: 0,
+            line_counts: None,
+            metadata: None,
+            ignored_count: 0,
+            error_count: 0,
         }
     }
 
-    fn add_directory(&mut self, directory: DirectoryObject) {
+    pub fn add_directory(&mut self, directory: DirectoryObject) {
         self.directories.push(directory);
     }
 
-    fn add_file(&mut self, file: FileObject) {
+    pub fn add_file(&mut self, file: FileObject) {
         self.files.push(file);
     }
+
+    /// Recalculate the file count and size from the files directly in this directory
+    pub fn update_totals(&mut self) {
+        self.file_count = self.files.len() as u64;
+        self.size = self.files.iter().map(|file| file.size).sum();
+        self.size_string = bytes_to_best_size(self.size);
+    }
+
+    /// Name of the directory without any leading path components, names of directories merged
+    /// by `compact_directories` are kept whole
+    pub fn base_name(&self) -> String {
+        if self.name != self.path && !self.name.contains('\\') {
+            return self.name.clone();
+        }
+
+        self.name
+            .rsplit(['/', '\\'])
+            .next()
+            .unwrap_or_default()
+            .to_string()
+    }
+
+    /// Size of all files in this directory and its subdirectories
+    pub fn total_size(&self) -> u64 {
+        self.size
+            + self
+                .directories
+                .iter()
+                .map(|directory| directory.total_size())
+                .sum::<u64>()
+    }
 }
 
 #[derive(Debug)]
@@ -44,10 +89,12 @@ pub struct FileObject {
     pub path: String,
     pub size: u64,
     pub size_string: String,
+    pub code_stats: Option<CodeStats>,
+    pub metadata: Option<EntryMetadata>,
 }
 
 impl FileObject {
-    fn new(
+    pub fn new(
         full_name: &String,
         name: &String,
         ending: String,
@@ -61,45 +108,73 @@ impl FileObject {
             path,
             size,
             size_string: bytes_to_best_size(size),
+            code_stats: None,
+            metadata: None,
         }
     }
 }
 
-pub fn scan(start_dir_path: &String, os: &OS) -> DirectoryObject {
-    let ignored_directories_setting = get_setting_from_config(ConfigOption::IgnoredDirectories, os);
-    let ignored_files_setting = get_setting_from_config(ConfigOption::IgnoredFiles, os);
+pub struct IgnoreLists {
+    pub directories: Vec<String>,
+    pub files: Vec<String>,
+}
+
+impl IgnoreLists {
+    pub fn from_config(os: &OS) -> IgnoreLists {
+        let ignored_directories_setting =
+            get_setting_from_config(ConfigOption::IgnoredDirectories, os);
+        let ignored_files_setting = get_setting_from_config(ConfigOption::IgnoredFiles, os);
 
-    let ignored_directories: Vec<&str> = ignored_directories_setting.value.split(",").collect();
-    let ignored_files: Vec<&str> = ignored_files_setting.value.split(",").collect();
+        IgnoreLists {
+            directories: split_setting_value(&ignored_directories_setting.value),
+            files: split_setting_value(&ignored_files_setting.value),
+        }
+    }
+
+    pub fn ignores_directory(&self, name: &str) -> bool {
+        self.directories.iter().any(|directory| directory == name)
+    }
+
+    pub fn ignores_file(&self, name: &str) -> bool {
+        self.files.iter().any(|file| file == name)
+    }
+}
+
+fn split_setting_value(value: &str) -> Vec<String> {
+    value.split(",").map(|v| v.to_string()).collect()
+}
+
+pub fn scan(start_dir_path: &String, os: &OS) -> DirectoryObject {
+    let ignored = IgnoreLists::from_config(os);
 
-    create_tree(start_dir_path, &ignored_directories, &ignored_files, os)
+    create_tree(start_dir_path, &ignored)
 }
 
-fn create_tree(
-    start_dir_path: &String,
-    ignored_directories: &Vec<&str>,
-    ignored_files: &Vec<&str>,
-    os: &OS,
-) -> DirectoryObject {
+pub fn create_tree(start_dir_path: &String, ignored: &IgnoreLists) -> DirectoryObject {
     let mut directory_stack: Vec<String> = Vec::new();
 
     let mut tree = DirectoryObject::new(
         &start_dir_path.split("/").last().unwrap().to_string(),
         start_dir_path,
     );
+    tree.metadata = read_metadata(start_dir_path);
 
-    let mut start_dir_contents = match fs::read_dir(start_dir_path) {
+    let start_dir_contents = match fs::read_dir(start_dir_path) {
         Ok(contents) => contents,
         Err(_) => {
+            tree.error_count += 1;
             return tree;
         }
     };
 
-    let mut file_count = 0;
-    let mut total_size = 0;
-
-    while let Some(entry) = start_dir_contents.next() {
-        let entry = entry.unwrap();
+    for entry in start_dir_contents {
+        let entry = match entry {
+            Ok(entry) => entry,
+            Err(_) => {
+                tree.error_count += 1;
+                continue;
+            }
+        };
         let entry_path = entry.path();
         let entry_name = entry_path
             .file_name()
@@ -109,51 +184,35 @@ fn create_tree(
             .to_string();
 
         if entry_path.is_dir() {
-            if ignored_directories.contains(&entry_name.as_str()) {
+            if ignored.ignores_directory(&entry_name) {
+                tree.ignored_count += 1;
                 continue;
             }
 
             directory_stack.push(entry_path.to_str().unwrap().to_string());
             continue;
-        } else {
-            if ignored_files.contains(&entry_name.as_str()) {
-                continue;
-            }
+        } else if ignored.ignores_file(&entry_name) {
+            tree.ignored_count += 1;
+            continue;
         }
 
-        file_count += 1;
-
-        let entry_size = match entry.metadata() {
-            Ok(metadata) => metadata.len(),
-            Err(_) => panic!("could not get metadata"),
-        };
-
-        total_size += entry_size;
-
-        let entry_ending = match entry_path.extension() {
-            Some(ending) => ending.to_str().unwrap().to_string(),
-            None => String::new(),
+        let metadata = match entry.metadata() {
+            Ok(metadata) => metadata,
+            Err(_) => {
+                tree.error_count += 1;
+                continue;
+            }
         };
 
-        let file = FileObject::new(
-            &entry_name,
-            &entry_name.replace(&format!(".{}", entry_ending).to_string(), ""),
-            entry_ending,
-            entry_path.to_str().unwrap().to_string(),
-            entry_size,
-        );
-
+        let mut file = file_from_path(&entry_path, metadata.len());
+        file.metadata = Some(EntryMetadata::from_metadata(&metadata));
         tree.add_file(file);
     }
 
-    tree.file_count = file_count;
-    tree.size = total_size;
-    tree.size_string = bytes_to_best_size(total_size);
+    tree.update_totals();
 
-    while !directory_stack.is_empty() {
-        let current_dir_path = directory_stack.pop().unwrap();
-
-        let sub_dir_tree = create_tree(&current_dir_path, ignored_directories, ignored_files, &os);
+    while let Some(current_dir_path) = directory_stack.pop() {
+        let sub_dir_tree = create_tree(&current_dir_path, ignored);
 
         tree.add_directory(sub_dir_tree);
     }
@@ -161,29 +220,45 @@ fn create_tree(
     tree
 }
 
-fn bytes_to_best_size(bytes: u64) -> String {
-    let mut size = bytes as f64;
-    let mut unit = "B";
+/// Read the metadata of an entry without following symbolic links
+pub fn read_metadata(path: &str) -> Option<EntryMetadata> {
+    fs::symlink_metadata(path)
+        .ok()
+        .map(|metadata| EntryMetadata::from_metadata(&metadata))
+}
 
-    if size > 1024.0 {
-        size /= 1024.0;
-        unit = "KB";
+/// Create a file from a name read from an imported file, which could be any string
+pub fn file_from_name(name: &str, entry_size: u64) -> Result<FileObject, String> {
+    if !is_valid_entry_name(name) {
+        return Err(format!("invalid file name \"{}\"", name));
     }
 
-    if size > 1024.0 {
-        size /= 1024.0;
-        unit = "MB";
-    }
+    Ok(file_from_path(Path::new(name), entry_size))
+}
 
-    if size > 1024.0 {
-        size /= 1024.0;
-        unit = "GB";
-    }
+/// Whether a name is a single entry of a directory, not empty, "." or ".." and without separators
+pub fn is_valid_entry_name(name: &str) -> bool {
+    !name.is_empty() && name != "." && name != ".." && !name.chars().any(std::path::is_separator)
+}
 
-    if size > 1024.0 {
-        size /= 1024.0;
-        unit = "TB";
-    }
+pub fn file_from_path(entry_path: &Path, entry_size: u64) -> FileObject {
+    let entry_name = entry_path
+        .file_name()
+        .unwrap()
+        .to_str()
+        .unwrap()
+        .to_string();
+
+    let entry_ending = match entry_path.extension() {
+        Some(ending) => ending.to_str().unwrap().to_string(),
+        None => String::new(),
+    };
 
-    format!("{:.2} {}", size, unit)
+    FileObject::new(
+        &entry_name,
+        &entry_name.replace(&format!(".{}", entry_ending).to_string(), ""),
+        entry_ending,
+        entry_path.to_str().unwrap().to_string(),
+        entry_size,
+    )
 }
@@ -0,0 +1,153 @@
+use crate::utils::format_count;
+use std::sync::OnceLock;
+
+pub const DEFAULT_PRECISION: usize = 2;
+
+/// How sizes are written everywhere, decided once at startup by `set_size_format`
+static SIZE_FORMAT: OnceLock<SizeFormat> = OnceLock::new();
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SizeUnits {
+    /// Powers of 1024 labeled KB, MB and GB, the format fmap has always used
+    Binary,
+    /// Powers of 1000 labeled kB, MB and GB
+    Si,
+    /// Powers of 1024 labeled KiB, MiB and GiB
+    Iec,
+    /// Exact byte counts with thousands separators
+    Bytes,
+}
+
+impl SizeUnits {
+    fn base(&self) -> f64 {
+        match self {
+            SizeUnits::Si => 1000.0,
+            _ => 1024.0,
+        }
+    }
+
+    fn labels(&self) -> [&'static str; 6] {
+        match self {
+            SizeUnits::Binary | SizeUnits::Bytes => ["B", "KB", "MB", "GB", "TB", "PB"],
+            SizeUnits::Si => ["B", "kB", "MB", "GB", "TB", "PB"],
+            SizeUnits::Iec => ["B", "KiB", "MiB", "GiB", "TiB", "PiB"],
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct SizeFormat {
+    pub units: SizeUnits,
+    /// Number of decimals shown for sizes of a kilobyte or more
+    pub precision: usize,
+}
+
+impl Default for SizeFormat {
+    fn default() -> Self {
+        SizeFormat {
+            units: SizeUnits::Binary,
+            precision: DEFAULT_PRECISION,
+        }
+    }
+}
+
+impl SizeFormat {
+    /// The format set at startup, the default format if none was set
+    pub fn current() -> SizeFormat {
+        SIZE_FORMAT.get().copied().unwrap_or_default()
+    }
+
+    pub fn format(&self, bytes: u64) -> String {
+        if self.units == SizeUnits::Bytes {
+            return format!("{} B", format_count(bytes));
+        }
+
+        let base = self.units.base();
+        let labels = self.units.labels();
+        let mut size = bytes as f64;
+        let mut unit = 0;
+
+        while size >= base && unit < labels.len() - 1 {
+            size /= base;
+            unit += 1;
+        }
+
+        // Bytes are whole numbers, but the original format has always shown them with decimals
+        if unit == 0 && self.units != SizeUnits::Binary {
+            format!("{} B", bytes)
+        } else {
+            format!("{:.*} {}", self.precision, size, labels[unit])
+        }
+    }
+}
+
+pub fn set_size_format(format: SizeFormat) {
+    let _ = SIZE_FORMAT.set(format);
+}
+
+/// Format a number of bytes with the units chosen at startup, like 2.34 KB
+pub fn bytes_to_best_size(bytes: u64) -> String {
+    SizeFormat::current().format(bytes)
+}
+
+/// Parse a size like 512, 10K, 1.5MB or 2GiB. Units with an i are powers of 1024, others
+/// are powers of 1000 with --si and powers of 1024 otherwise, the same way sizes are shown
+pub fn parse_size(value: &str) -> Option<u64> {
+    let value = value.trim().to_lowercase();
+    let number_end = value
+        .find(|c: char| !c.is_ascii_digit() && c != '.')
+        .unwrap_or(value.len());
+    let (number, unit) = value.split_at(number_end);
+
+    let unit = unit.trim();
+    let (prefix, base) = match unit.strip_suffix("ib") {
+        Some(prefix) => (prefix, 1024.0),
+        None => (
+            unit.strip_suffix('b').unwrap_or(unit),
+            SizeFormat::current().units.base(),
+        ),
+    };
+
+    let exponent = match prefix {
+        "" => 0,
+        "k" => 1,
+        "m" => 2,
+        "g" => 3,
+        "t" => 4,
+        "p" => 5,
+        _ => return None,
+    };
+
+    let number = number.parse::<f64>().ok()?;
+    Some((number * f64::powi(base, exponent)) as u64)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::parse_size;
+
+    #[test]
+    fn parses_plain_numbers() {
+        assert_eq!(parse_size("0"), Some(0));
+        assert_eq!(parse_size("512"), Some(512));
+        assert_eq!(parse_size(" 512 "), Some(512));
+        assert_eq!(parse_size("512b"), Some(512));
+    }
+
+    #[test]
+    fn parses_units() {
+        assert_eq!(parse_size("10K"), Some(10 * 1024));
+        assert_eq!(parse_size("10kb"), Some(10 * 1024));
+        assert_eq!(parse_size("1.5MB"), Some(1024 * 1024 * 3 / 2));
+        assert_eq!(parse_size("2 GiB"), Some(2 * 1024 * 1024 * 1024));
+        assert_eq!(parse_size("1t"), Some(1024u64.pow(4)));
+        assert_eq!(parse_size("1pib"), Some(1024u64.pow(5)));
+    }
+
+    #[test]
+    fn rejects_malformed_sizes() {
+        for value in ["", "k", "-1", "1.2.3", "10x", "10 kbb", "1e3", "ten"] {
+            assert_eq!(parse_size(value), None, "{}", value);
+        }
+    }
+}
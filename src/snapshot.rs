@@ -0,0 +1,167 @@
+use crate::json::JsonValue;
+use crate::report::{report_from_json, report_to_json, ScanReport};
+use crate::scan::{file_from_name, is_valid_entry_name, scan, DirectoryObject, FileObject};
+use crate::OS;
+use std::fs;
+use std::path::Path;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+const SNAPSHOT_VERSION: u64 = 1;
+
+pub fn snapshot_to_json(tree: &DirectoryObject, report: &ScanReport) -> JsonValue {
+    let created = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_secs())
+        .unwrap_or(0);
+
+    JsonValue::object()
+        .with("version", SNAPSHOT_VERSION.into())
+        .with("created", created.into())
+        .with("report", report_to_json(report))
+        .with("tree", directory_to_json(tree))
+}
+
+pub fn directory_to_json(directory: &DirectoryObject) -> JsonValue {
+    JsonValue::object()
+        .with("name", directory.name.as_str().into())
+        .with("path", directory.path.as_str().into())
+        .with("size", directory.size.into())
+        .with("file_count", directory.file_count.into())
+        .with(
+            "files",
+            directory
+                .files
+                .iter()
+                .map(file_to_json)
+                .collect::<Vec<JsonValue>>()
+                .into(),
+        )
+        .with(
+            "directories",
+            directory
+                .directories
+                .iter()
+                .map(directory_to_json)
+                .collect::<Vec<JsonValue>>()
+                .into(),
+        )
+}
+
+fn file_to_json(file: &FileObject) -> JsonValue {
+    JsonValue::object()
+        .with("name", file.full_name.as_str().into())
+        .with("path", file.path.as_str().into())
+        .with("size", file.size.into())
+}
+
+/// Read the tree of a snapshot along with the report of the scan it was taken from, the
+/// report is recounted from the tree for snapshots saved without one
+pub fn snapshot_from_json(snapshot: &JsonValue) -> Result<(DirectoryObject, ScanReport), String> {
+    let version = snapshot
+        .get("version")
+        .and_then(|version| version.as_u64())
+        .ok_or("snapshot is missing a version")?;
+
+    if version > SNAPSHOT_VERSION {
+        return Err(format!(
+            "snapshot version {} is newer than the supported version {}",
+            version, SNAPSHOT_VERSION
+        ));
+    }
+
+    let tree = snapshot.get("tree").ok_or("snapshot is missing a tree")?;
+    let tree = directory_from_json(tree)?;
+    let report = snapshot
+        .get("report")
+        .and_then(report_from_json)
+        .unwrap_or_else(|| ScanReport::from_tree(&tree, Duration::ZERO));
+
+    Ok((tree, report))
+}
+
+/// Recursion follows the nesting of the JSON, which the parser already keeps within a limit
+fn directory_from_json(value: &JsonValue) -> Result<DirectoryObject, String> {
+    let name = get_string(value, "name")?;
+    let path = get_string(value, "path")?;
+    let mut directory = DirectoryObject::new(&name, &path);
+
+    for file in get_array(value, "files")? {
+        directory.add_file(file_from_json(file)?);
+    }
+
+    for sub_directory in get_array(value, "directories")? {
+        let sub_directory = directory_from_json(sub_directory)?;
+        if !is_valid_entry_name(&sub_directory.name) {
+            return Err(format!("invalid directory name \"{}\"", sub_directory.name));
+        }
+        directory.add_directory(sub_directory);
+    }
+
+    directory.update_totals();
+
+    Ok(directory)
+}
+
+fn file_from_json(value: &JsonValue) -> Result<FileObject, String> {
+    let full_name = get_string(value, "name")?;
+    let path = get_string(value, "path")?;
+    let size = value
+        .get("size")
+        .and_then(|size| size.as_u64())
+        .ok_or(format!("file {} is missing a size", full_name))?;
+
+    let mut file = file_from_name(&full_name, size)?;
+    file.path = path;
+
+    Ok(file)
+}
+
+fn get_string(value: &JsonValue, key: &str) -> Result<String, String> {
+    value
+        .get(key)
+        .and_then(|value| value.as_str())
+        .map(|value| value.to_string())
+        .ok_or(format!("snapshot entry is missing \"{}\"", key))
+}
+
+fn get_array<'a>(value: &'a JsonValue, key: &str) -> Result<&'a Vec<JsonValue>, String> {
+    value
+        .get(key)
+        .and_then(|value| value.as_array())
+        .ok_or(format!("snapshot entry is missing \"{}\"", key))
+}
+
+pub fn save_snapshot(
+    tree: &DirectoryObject,
+    report: &ScanReport,
+    output_path: &str,
+) -> Result<(), String> {
+    fs::write(
+        output_path,
+        snapshot_to_json(tree, report).to_pretty_string(),
+    )
+    .map_err(|e| format!("failed to write snapshot to {}: {}", output_path, e))
+}
+
+pub fn load_snapshot(snapshot_path: &str) -> Result<(DirectoryObject, ScanReport), String> {
+    let content = fs::read_to_string(snapshot_path)
+        .map_err(|e| format!("failed to read snapshot {}: {}", snapshot_path, e))?;
+    let snapshot = JsonValue::parse(&content)
+        .map_err(|e| format!("failed to parse snapshot {}: {}", snapshot_path, e))?;
+    snapshot_from_json(&snapshot)
+}
+
+/// Load a tree from either a saved snapshot file or a live directory
+pub fn load_tree(source: &String, os: &OS) -> Result<(DirectoryObject, ScanReport), String> {
+    let source_path = Path::new(source);
+    if source_path.is_file() {
+        load_snapshot(source)
+    } else if source_path.is_dir() {
+        let scan_start = Instant::now();
+        let tree = scan(source, os);
+        let report = ScanReport::from_tree(&tree, scan_start.elapsed());
+        Ok((tree, report))
+    } else {
+        Err(format!("{} is not a directory or snapshot file", source))
+    }
+}
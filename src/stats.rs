@@ -0,0 +1,186 @@
+use crate::charset::percentage_bar;
+use crate::json::JsonValue;
+use crate::scan::{DirectoryObject, FileObject};
+use crate::size::bytes_to_best_size;
+use crate::styles::TextStyle;
+use crate::utils::relative_path;
+use std::collections::HashMap;
+
+const NO_EXTENSION: &str = "(none)";
+const BAR_WIDTH: usize = 20;
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum StatsSortOrder {
+    Size,
+    Count,
+}
+
+impl StatsSortOrder {
+    pub fn from_string(string: &str) -> Option<StatsSortOrder> {
+        match string {
+            "size" => Some(StatsSortOrder::Size),
+            "count" => Some(StatsSortOrder::Count),
+            _ => None,
+        }
+    }
+}
+
+pub struct ExtensionStats {
+    pub extension: String,
+    pub count: u64,
+    pub total_size: u64,
+    pub largest_path: String,
+    pub largest_size: u64,
+}
+
+impl ExtensionStats {
+    pub fn average_size(&self) -> u64 {
+        self.total_size.checked_div(self.count).unwrap_or(0)
+    }
+}
+
+/// Group every file in the tree by its extension
+pub fn collect_extension_stats(
+    tree: &DirectoryObject,
+    sort_order: StatsSortOrder,
+) -> Vec<ExtensionStats> {
+    let mut stats: HashMap<String, ExtensionStats> = HashMap::new();
+    add_directory_stats(tree, tree, &mut stats);
+
+    let mut stats: Vec<ExtensionStats> = stats.into_values().collect();
+    match sort_order {
+        StatsSortOrder::Size => stats.sort_by(|a, b| {
+            b.total_size
+                .cmp(&a.total_size)
+                .then(a.extension.cmp(&b.extension))
+        }),
+        StatsSortOrder::Count => {
+            stats.sort_by(|a, b| b.count.cmp(&a.count).then(a.extension.cmp(&b.extension)))
+        }
+    }
+
+    stats
+}
+
+fn add_directory_stats(
+    root: &DirectoryObject,
+    directory: &DirectoryObject,
+    stats: &mut HashMap<String, ExtensionStats>,
+) {
+    for file in &directory.files {
+        add_file_stats(root, file, stats);
+    }
+
+    for sub_directory in &directory.directories {
+        add_directory_stats(root, sub_directory, stats);
+    }
+}
+
+fn add_file_stats(
+    root: &DirectoryObject,
+    file: &FileObject,
+    stats: &mut HashMap<String, ExtensionStats>,
+) {
+    let extension = if file.ending.is_empty() {
+        NO_EXTENSION.to_string()
+    } else {
+        file.ending.to_lowercase()
+    };
+
+    let entry = stats
+        .entry(extension.clone())
+        .or_insert_with(|| ExtensionStats {
+            extension,
+            count: 0,
+            total_size: 0,
+            largest_path: String::new(),
+            largest_size: 0,
+        });
+
+    entry.count += 1;
+    entry.total_size += file.size;
+    if entry.largest_path.is_empty() || file.size > entry.largest_size {
+        entry.largest_path = relative_path(&root.path, &file.path);
+        entry.largest_size = file.size;
+    }
+}
+
+pub fn display_stats(stats: &[ExtensionStats], sort_order: StatsSortOrder) {
+    let total_size: u64 = stats.iter().map(|stat| stat.total_size).sum();
+    let total_count: u64 = stats.iter().map(|stat| stat.count).sum();
+    let extension_width = stats
+        .iter()
+        .map(|stat| stat.extension.chars().count())
+        .max()
+        .unwrap_or(0)
+        .max("extension".len());
+
+    println!(
+        "{}{:<extension_width$} {:>7} {:>11} {:>11} {:>11}  {:<width$}  largest{}",
+        TextStyle::Bold.to_ansi_style_string(),
+        "extension",
+        "files",
+        "total",
+        "average",
+        "max",
+        "share",
+        TextStyle::Reset.to_ansi_style_string(),
+        width = BAR_WIDTH + 9,
+    );
+
+    for stat in stats {
+        let (bar, percentage) = match sort_order {
+            StatsSortOrder::Size => percentage_bar(stat.total_size, total_size, BAR_WIDTH),
+            StatsSortOrder::Count => percentage_bar(stat.count, total_count, BAR_WIDTH),
+        };
+
+        println!(
+            "{:<extension_width$} {:>7} {:>11} {:>11} {:>11}  [{}] {:>5.1}%  {}",
+            stat.extension,
+            stat.count,
+            bytes_to_best_size(stat.total_size),
+            bytes_to_best_size(stat.average_size()),
+            bytes_to_best_size(stat.largest_size),
+            bar,
+            percentage,
+            stat.largest_path,
+        );
+    }
+
+    println!();
+    println!(
+        "{} files, {} extensions, {} total",
+        total_count,
+        stats.len(),
+        bytes_to_best_size(total_size)
+    );
+}
+
+pub fn stats_to_json(stats: &[ExtensionStats]) -> JsonValue {
+    let total_size: u64 = stats.iter().map(|stat| stat.total_size).sum();
+    let total_count: u64 = stats.iter().map(|stat| stat.count).sum();
+
+    JsonValue::object()
+        .with("total_files", total_count.into())
+        .with("total_size", total_size.into())
+        .with(
+            "extensions",
+            stats
+                .iter()
+                .map(|stat| {
+                    JsonValue::object()
+                        .with("extension", stat.extension.as_str().into())
+                        .with("count", stat.count.into())
+                        .with("total_size", stat.total_size.into())
+                        .with("average_size", stat.average_size().into())
+                        .with(
+                            "largest",
+                            JsonValue::object()
+                                .with("path", stat.largest_path.as_str().into())
+                                .with("size", stat.largest_size.into()),
+                        )
+                })
+                .collect::<Vec<JsonValue>>()
+                .into(),
+        )
+}
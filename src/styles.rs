@@ -1,3 +1,127 @@
+use std::env;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::OnceLock;
+
+/// Whether escape codes are written, decided once at startup by `set_color_choice`
+static COLORS_ENABLED: AtomicBool = AtomicBool::new(true);
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ColorChoice {
+    Auto,
+    Always,
+    Never,
+}
+
+impl ColorChoice {
+    pub fn from_string(string: &str) -> Option<ColorChoice> {
+        match string {
+            "auto" => Some(ColorChoice::Auto),
+            "always" => Some(ColorChoice::Always),
+            "never" => Some(ColorChoice::Never),
+            _ => None,
+        }
+    }
+}
+
+/// Set the global color policy, with auto colors are used if the output is a terminal and
+/// NO_COLOR, CLICOLOR, CLICOLOR_FORCE and TERM do not say otherwise
+pub fn set_color_choice(choice: ColorChoice, output_is_terminal: bool) {
+    let enabled = match choice {
+        ColorChoice::Always => true,
+        ColorChoice::Never => false,
+        ColorChoice::Auto => detect_colors(output_is_terminal),
+    };
+
+    COLORS_ENABLED.store(enabled, Ordering::Relaxed);
+}
+
+pub fn colors_enabled() -> bool {
+    COLORS_ENABLED.load(Ordering::Relaxed)
+}
+
+fn detect_colors(output_is_terminal: bool) -> bool {
+    let is_set = |name: &str| env::var(name).is_ok_and(|value| !value.is_empty());
+
+    // https://no-color.org
+    if is_set("NO_COLOR") {
+        return false;
+    }
+
+    // https://bixense.com/clicolors
+    if is_set("CLICOLOR_FORCE") && env::var("CLICOLOR_FORCE").as_deref() != Ok("0") {
+        return true;
+    }
+    if env::var("CLICOLOR").as_deref() == Ok("0") {
+        return false;
+    }
+
+    if env::var("TERM").as_deref() == Ok("dumb") {
+        return false;
+    }
+
+    output_is_terminal
+}
+
+/// Escape codes are left out when colors are disabled
+fn escape_code(code: &str) -> String {
+    if colors_enabled() {
+        code.to_string()
+    } else {
+        String::new()
+    }
+}
+
+/// Number of colors the terminal can show, detected from COLORTERM and TERM
+#[derive(Debug, Clone, Copy, PartialEq, PartialOrd)]
+pub enum ColorDepth {
+    Basic,
+    Ansi256,
+    TrueColor,
+}
+
+impl ColorDepth {
+    pub fn detect() -> ColorDepth {
+        *COLOR_DEPTH.get_or_init(|| {
+            let color_term = env::var("COLORTERM").unwrap_or_default();
+            let term = env::var("TERM").unwrap_or_default();
+
+            if color_term == "truecolor" || color_term == "24bit" {
+                ColorDepth::TrueColor
+            } else if term.contains("256color") || term.contains("direct") {
+                ColorDepth::Ansi256
+            } else {
+                ColorDepth::Basic
+            }
+        })
+    }
+}
+
+static COLOR_DEPTH: OnceLock<ColorDepth> = OnceLock::new();
+
+/// RGB values of the 16 basic colors, from the default xterm palette
+const BASIC_PALETTE: [(u8, u8, u8); 16] = [
+    (0, 0, 0),
+    (205, 0, 0),
+    (0, 205, 0),
+    (205, 205, 0),
+    (0, 0, 238),
+    (205, 0, 205),
+    (0, 205, 205),
+    (229, 229, 229),
+    (127, 127, 127),
+    (255, 0, 0),
+    (0, 255, 0),
+    (255, 255, 0),
+    (92, 92, 255),
+    (255, 0, 255),
+    (0, 255, 255),
+    (255, 255, 255),
+];
+
+/// Levels of each channel in the 6x6x6 color cube of the 256 color palette
+const CUBE_LEVELS: [u8; 6] = [0, 95, 135, 175, 215, 255];
+
+#[derive(Debug, Clone, Copy, PartialEq)]
 pub enum Color {
     Black,
     Red,
@@ -15,35 +139,61 @@ pub enum Color {
     BrightPurple,
     BrightCyan,
     BrightWhite,
+    /// A color of the 256 color palette
+    Indexed(u8),
+    /// A 24 bit color
+    Rgb(u8, u8, u8),
     Reset,
 }
 
 impl Color {
     pub fn to_ansi_color_string(&self) -> String {
-        match self {
-            Color::Black => "\u{001b}[30m",
-            Color::Red => "\u{001b}[31m",
-            Color::Green => "\u{001b}[32m",
-            Color::Yellow => "\u{001b}[33m",
-            Color::Blue => "\u{001b}[34m",
-            Color::Purple => "\u{001b}[35m",
-            Color::Cyan => "\u{001b}[36m",
-            Color::White => "\u{001b}[37m",
-            Color::BrightBlack => "\u{001b}[90m",
-            Color::BrightRed => "\u{001b}[91m",
-            Color::BrightGreen => "\u{001b}[92m",
-            Color::BrightYellow => "\u{001b}[93m",
-            Color::BrightBlue => "\u{001b}[94m",
-            Color::BrightPurple => "\u{001b}[95m",
-            Color::BrightCyan => "\u{001b}[96m",
-            Color::BrightWhite => "\u{001b}[97m",
-            Color::Reset => "\u{001b}[0m",
+        escape_code(&format!("\u{001b}[{}m", self.sgr_parameters(false)))
+    }
+
+    pub fn to_ansi_background_color_string(&self) -> String {
+        escape_code(&format!("\u{001b}[{}m", self.sgr_parameters(true)))
+    }
+
+    /// Parameters of the escape code that sets this color, like 31 or 38;5;208, downsampled to
+    /// the colors the terminal can show
+    pub fn sgr_parameters(&self, background: bool) -> String {
+        let layer = if background { 48 } else { 38 };
+
+        match self.downsample(ColorDepth::detect()) {
+            Color::Reset => "0".to_string(),
+            Color::Indexed(index) => format!("{};5;{}", layer, index),
+            Color::Rgb(r, g, b) => format!("{};2;{};{};{}", layer, r, g, b),
+            color => {
+                let index = color.basic_index();
+                let code = match (index < 8, background) {
+                    (true, false) => 30 + index,
+                    (true, true) => 40 + index,
+                    (false, false) => 90 + index - 8,
+                    (false, true) => 100 + index - 8,
+                };
+                code.to_string()
+            }
         }
-        .to_string()
     }
 
+    /// Parse a color name like red or bright-blue, a 256 color palette index like 208 or a
+    /// hex color like #ff8800 or #f80
     pub fn from_string(string: String) -> Color {
-        match string.as_str() {
+        Color::parse(&string).unwrap_or(Color::Reset)
+    }
+
+    /// Like `from_string`, but unknown colors are `None` instead of `Color::Reset`
+    pub fn parse(string: &str) -> Option<Color> {
+        if let Ok(index) = string.parse::<u8>() {
+            return Some(Color::Indexed(index));
+        }
+
+        if let Some(hex) = string.strip_prefix('#') {
+            return parse_hex_color(hex);
+        }
+
+        let color = match string {
             "black" => Color::Black,
             "red" => Color::Red,
             "green" => Color::Green,
@@ -61,31 +211,141 @@ impl Color {
             "bright-cyan" => Color::BrightCyan,
             "bright-white" => Color::BrightWhite,
             "reset" => Color::Reset,
-            _ => Color::Reset,
-        }
+            _ => return None,
+        };
+        Some(color)
     }
 
-    pub fn to_ansi_background_color_string(&self) -> String {
+    /// Index of a basic color in the 16 color palette
+    fn basic_index(&self) -> u8 {
         match self {
-            Color::Black => "\u{001b}[40m",
-            Color::Red => "\u{001b}[41m",
-            Color::Green => "\u{001b}[42m",
-            Color::Yellow => "\u{001b}[43m",
-            Color::Blue => "\u{001b}[44m",
-            Color::Purple => "\u{001b}[45m",
-            Color::Cyan => "\u{001b}[46m",
-            Color::White => "\u{001b}[47m",
-            Color::BrightBlack => "\u{001b}[100m",
-            Color::BrightRed => "\u{001b}[101m",
-            Color::BrightGreen => "\u{001b}[102m",
-            Color::BrightYellow => "\u{001b}[103m",
-            Color::BrightBlue => "\u{001b}[104m",
-            Color::BrightPurple => "\u{001b}[105m",
-            Color::BrightCyan => "\u{001b}[106m",
-            Color::BrightWhite => "\u{001b}[107m",
-            Color::Reset => "\u{001b}[0m",
+            Color::Black => 0,
+            Color::Red => 1,
+            Color::Green => 2,
+            Color::Yellow => 3,
+            Color::Blue => 4,
+            Color::Purple => 5,
+            Color::Cyan => 6,
+            Color::White => 7,
+            Color::BrightBlack => 8,
+            Color::BrightRed => 9,
+            Color::BrightGreen => 10,
+            Color::BrightYellow => 11,
+            Color::BrightBlue => 12,
+            Color::BrightPurple => 13,
+            Color::BrightCyan => 14,
+            Color::BrightWhite => 15,
+            Color::Indexed(index) => *index,
+            Color::Rgb(..) | Color::Reset => 0,
+        }
+    }
+
+    fn from_basic_index(index: u8) -> Color {
+        [
+            Color::Black,
+            Color::Red,
+            Color::Green,
+            Color::Yellow,
+            Color::Blue,
+            Color::Purple,
+            Color::Cyan,
+            Color::White,
+            Color::BrightBlack,
+            Color::BrightRed,
+            Color::BrightGreen,
+            Color::BrightYellow,
+            Color::BrightBlue,
+            Color::BrightPurple,
+            Color::BrightCyan,
+            Color::BrightWhite,
+        ][index as usize % 16]
+    }
+
+    /// Convert the color to the closest one the terminal can show
+    pub fn downsample(self, depth: ColorDepth) -> Color {
+        match (self, depth) {
+            (Color::Rgb(r, g, b), ColorDepth::Ansi256) => Color::Indexed(rgb_to_ansi256(r, g, b)),
+            (Color::Rgb(r, g, b), ColorDepth::Basic) => {
+                Color::from_basic_index(rgb_to_basic(r, g, b))
+            }
+            (Color::Indexed(index), ColorDepth::Basic) if index < 16 => {
+                Color::from_basic_index(index)
+            }
+            (Color::Indexed(index), ColorDepth::Basic) => {
+                let (r, g, b) = ansi256_to_rgb(index);
+                Color::from_basic_index(rgb_to_basic(r, g, b))
+            }
+            (color, _) => color,
+        }
+    }
+}
+
+fn parse_hex_color(hex: &str) -> Option<Color> {
+    let channel = |digits: &str| u8::from_str_radix(digits, 16).ok();
+
+    match hex.len() {
+        6 => Some(Color::Rgb(
+            channel(&hex[0..2])?,
+            channel(&hex[2..4])?,
+            channel(&hex[4..6])?,
+        )),
+        // #f80 is short for #ff8800
+        3 => Some(Color::Rgb(
+            channel(&hex[0..1])? * 17,
+            channel(&hex[1..2])? * 17,
+            channel(&hex[2..3])? * 17,
+        )),
+        _ => None,
+    }
+}
+
+fn color_distance(a: (u8, u8, u8), b: (u8, u8, u8)) -> u32 {
+    let difference = |x: u8, y: u8| (x as i32 - y as i32).pow(2) as u32;
+    difference(a.0, b.0) + difference(a.1, b.1) + difference(a.2, b.2)
+}
+
+fn rgb_to_basic(r: u8, g: u8, b: u8) -> u8 {
+    (0..16)
+        .min_by_key(|&index| color_distance((r, g, b), BASIC_PALETTE[index]))
+        .unwrap_or(0) as u8
+}
+
+fn ansi256_to_rgb(index: u8) -> (u8, u8, u8) {
+    match index {
+        0..=15 => BASIC_PALETTE[index as usize],
+        16..=231 => {
+            let cube_index = index - 16;
+            (
+                CUBE_LEVELS[(cube_index / 36) as usize],
+                CUBE_LEVELS[(cube_index / 6 % 6) as usize],
+                CUBE_LEVELS[(cube_index % 6) as usize],
+            )
+        }
+        _ => {
+            let level = 8 + (index - 232) * 10;
+            (level, level, level)
         }
-        .to_string()
+    }
+}
+
+/// Closest color of the 256 color palette, either from the color cube or the gray ramp
+fn rgb_to_ansi256(r: u8, g: u8, b: u8) -> u8 {
+    let nearest_level = |value: u8| {
+        (0..6)
+            .min_by_key(|&level| (CUBE_LEVELS[level] as i32 - value as i32).abs())
+            .unwrap_or(0) as u8
+    };
+    let cube_index = 16 + 36 * nearest_level(r) + 6 * nearest_level(g) + nearest_level(b);
+
+    let average = (r as u32 + g as u32 + b as u32) / 3;
+    let gray_index = 232 + ((average.saturating_sub(8) + 5) / 10).min(23) as u8;
+
+    if color_distance((r, g, b), ansi256_to_rgb(gray_index))
+        < color_distance((r, g, b), ansi256_to_rgb(cube_index))
+    {
+        gray_index
+    } else {
+        cube_index
     }
 }
 
@@ -103,22 +363,31 @@ pub enum TextStyle {
 
 impl TextStyle {
     pub fn to_ansi_style_string(&self) -> String {
+        escape_code(&format!("\u{001b}[{}m", self.sgr_parameter()))
+    }
+
+    /// Parameter of the escape code that sets this style, like 1 for bold
+    pub fn sgr_parameter(&self) -> &'static str {
         match self {
-            TextStyle::Bold => "\u{001b}[1m",
-            TextStyle::Dim => "\u{001b}[2m",
-            TextStyle::Italic => "\u{001b}[3m",
-            TextStyle::Underline => "\u{001b}[4m",
-            TextStyle::Blink => "\u{001b}[5m",
-            TextStyle::Invert => "\u{001b}[7m",
-            TextStyle::Hidden => "\u{001b}[8m",
-            TextStyle::Strikethrough => "\u{001b}[9m",
-            TextStyle::Reset => "\u{001b}[0m",
+            TextStyle::Bold => "1",
+            TextStyle::Dim => "2",
+            TextStyle::Italic => "3",
+            TextStyle::Underline => "4",
+            TextStyle::Blink => "5",
+            TextStyle::Invert => "7",
+            TextStyle::Hidden => "8",
+            TextStyle::Strikethrough => "9",
+            TextStyle::Reset => "0",
         }
-        .to_string()
     }
 
     pub fn from_string(string: String) -> TextStyle {
-        match string.as_str() {
+        TextStyle::parse(&string).unwrap_or(TextStyle::Reset)
+    }
+
+    /// Like `from_string`, but unknown styles are `None` instead of `TextStyle::Reset`
+    pub fn parse(string: &str) -> Option<TextStyle> {
+        let style = match string {
             "bold" => TextStyle::Bold,
             "dim" => TextStyle::Dim,
             "italic" => TextStyle::Italic,
@@ -128,8 +397,9 @@ impl TextStyle {
             "hidden" => TextStyle::Hidden,
             "strikethrough" => TextStyle::Strikethrough,
             "reset" => TextStyle::Reset,
-            _ => TextStyle::Reset,
-        }
+            _ => return None,
+        };
+        Some(style)
     }
 }
 
@@ -1,3 +1,6 @@
+use crate::charset::DEFAULT_CHARSET;
+use crate::icons::DEFAULT_ICON_SET;
+use crate::theme::DEFAULT_THEME;
 use crate::utils::{exit_with_error, is_valid_name, Object};
 use crate::OS;
 use std::fs::{read_to_string, File};
@@ -23,6 +26,16 @@ const DEFAULT_IGNORED_FILES: [&str; 0] = [];
 pub enum ConfigOption {
     IgnoredDirectories,
     IgnoredFiles,
+    /// Name of the theme used when --theme is not passed
+    Theme,
+    /// Comma separated list of key=style pairs applied on top of the theme
+    ThemeStyles,
+    /// Icons used when --icon-set is not passed, nerd or unicode
+    IconSet,
+    /// Comma separated list of key=icon pairs applied on top of the icon set
+    Icons,
+    /// Characters used to draw trees when --charset is not passed
+    Charset,
 }
 
 impl ConfigOption {
@@ -30,6 +43,11 @@ impl ConfigOption {
         match self {
             Self::IgnoredDirectories => "ignored-directories".to_string(),
             Self::IgnoredFiles => "ignored-files".to_string(),
+            Self::Theme => "theme".to_string(),
+            Self::ThemeStyles => "theme-styles".to_string(),
+            Self::IconSet => "icon-set".to_string(),
+            Self::Icons => "icons".to_string(),
+            Self::Charset => "charset".to_string(),
         }
     }
 }
@@ -88,6 +106,17 @@ fn write_default_config_to_file(mut config_file: File) -> std::io::Result<()> {
     value_line.pop();
     config_file.write_all(value_line.as_bytes())?;
 
+    config_file.write_all(b"\ntheme-styles\n")?;
+    config_file.write_all(b"\ntheme\n")?;
+    config_file.write_all(DEFAULT_THEME.as_bytes())?;
+
+    config_file.write_all(b"\nicons\n")?;
+    config_file.write_all(b"\nicon-set\n")?;
+    config_file.write_all(DEFAULT_ICON_SET.as_bytes())?;
+
+    config_file.write_all(b"\ncharset\n")?;
+    config_file.write_all(DEFAULT_CHARSET.as_bytes())?;
+
     Ok(())
 }
 
@@ -99,32 +128,39 @@ pub fn get_setting_from_config(config_option: ConfigOption, os: &OS) -> Setting
             true,
         )
     }
+
+    match get_optional_setting_from_config(config_option, os) {
+        Some(setting) => setting,
+        None => {
+            exit_with_error("failed to find config option", true);
+            // will never reach this
+            Setting::new("".to_string(), 0, "".to_string())
+        }
+    }
+}
+
+/// Like `get_setting_from_config`, but for settings that older config files may not have
+pub fn get_optional_setting_from_config(config_option: ConfigOption, os: &OS) -> Option<Setting> {
+    let config_content = read_to_string(get_config_path(os)).ok()?;
     let key = config_option.get_config_option_key();
 
-    for (index, line) in read_to_string(&config_file_path)
-        .unwrap()
-        .lines()
-        .enumerate()
-    {
+    for (index, line) in config_content.lines().enumerate() {
         if index % 2 != 1 && line == key {
-            let config_content = read_to_string(&config_file_path).unwrap();
             let value_line = config_content.lines().nth(index + 1).unwrap_or("None");
-            return Setting::new(key, index, value_line.to_string());
+            return Some(Setting::new(key, index, value_line.to_string()));
         }
     }
 
-    exit_with_error("failed to find config option", true);
-    // will never reach this
-    Setting::new("".to_string(), 0, "".to_string())
+    None
 }
 
 pub fn remove_value_from_setting(config_option: ConfigOption, value_setting: &str, os: &OS) {
     let object_type = match config_option {
         ConfigOption::IgnoredDirectories => Object::Directory,
-        ConfigOption::IgnoredFiles => Object::File,
+        _ => Object::File,
     };
 
-    if !is_valid_name(&value_setting, object_type) {
+    if !is_valid_name(value_setting, object_type) {
         exit_with_error(
             format!("{} is not a valid name", value_setting).as_str(),
             false,
@@ -151,10 +187,10 @@ pub fn remove_value_from_setting(config_option: ConfigOption, value_setting: &st
 pub fn add_value_to_setting(config_option: ConfigOption, value_setting: &str, os: &OS) {
     let object_type = match config_option {
         ConfigOption::IgnoredDirectories => Object::Directory,
-        ConfigOption::IgnoredFiles => Object::File,
+        _ => Object::File,
     };
 
-    if !is_valid_name(&value_setting, object_type) {
+    if !is_valid_name(value_setting, object_type) {
         exit_with_error(
             format!("{} is not a valid name", value_setting).as_str(),
             false,
@@ -196,7 +232,7 @@ pub fn get_user_home_dir(os: &OS) -> String {
         OS::Windows => "USERPROFILE",
         OS::Mac => "HOME",
     };
-    std::env::var(format!("{}", user_dir)).unwrap()
+    std::env::var(user_dir).unwrap()
 }
 
 fn path_exists(path: &String) -> bool {
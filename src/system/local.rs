@@ -73,7 +73,7 @@ fn add_registry_path(new_path: &str) -> std::io::Result<()> {
 
     // Prepare the command to modify the registry
     let status = Command::new("reg")
-        .args(&[
+        .args([
             "ADD",
             "HKLM\\SYSTEM\\CurrentControlSet\\Control\\Session Manager\\Environment",
             "/v",
@@ -87,10 +87,7 @@ fn add_registry_path(new_path: &str) -> std::io::Result<()> {
         .status()?;
 
     if !status.success() {
-        return Err(std::io::Error::new(
-            std::io::ErrorKind::Other,
-            "Failed to modify registry",
-        ));
+        return Err(std::io::Error::other("Failed to modify registry"));
     }
 
     Ok(())
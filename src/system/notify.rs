@@ -0,0 +1,676 @@
+use std::collections::HashSet;
+use std::time::Duration;
+
+/// Changes reported by the operating system since the last wait
+pub enum Changes {
+    /// Directories with entries that were added, removed or modified, empty if nothing changed
+    Directories(HashSet<String>),
+    /// Events were lost, so any directory could have changed
+    Unknown,
+}
+
+/// Receives file system change notifications from the operating system, inotify on Linux,
+/// FSEvents on macOS and ReadDirectoryChangesW on Windows
+pub struct ChangeNotifier {
+    #[cfg(target_os = "linux")]
+    inotify: inotify::Inotify,
+    #[cfg(target_os = "macos")]
+    stream: fsevents::EventStream,
+    #[cfg(windows)]
+    changes: directory_changes::DirectoryChanges,
+}
+
+#[cfg(target_os = "linux")]
+impl ChangeNotifier {
+    pub fn new(root: &str) -> Result<ChangeNotifier, String> {
+        let mut inotify = inotify::Inotify::new()?;
+        inotify.sync_directories(&[root.to_string()])?;
+        Ok(ChangeNotifier { inotify })
+    }
+
+    /// Watch exactly the given directories, inotify watches every directory on its own
+    pub fn sync_directories(&mut self, directories: &[String]) -> Result<(), String> {
+        self.inotify.sync_directories(directories)
+    }
+
+    /// Wait until something changes or the timeout passes
+    pub fn wait(&mut self, timeout: Duration) -> Result<Changes, String> {
+        self.inotify.wait(timeout)
+    }
+}
+
+#[cfg(target_os = "macos")]
+impl ChangeNotifier {
+    pub fn new(root: &str) -> Result<ChangeNotifier, String> {
+        Ok(ChangeNotifier {
+            stream: fsevents::EventStream::new(root)?,
+        })
+    }
+
+    /// FSEvents watches the whole tree below the root, so there is nothing to update
+    pub fn sync_directories(&mut self, _directories: &[String]) -> Result<(), String> {
+        Ok(())
+    }
+
+    /// Wait until something changes or the timeout passes
+    pub fn wait(&mut self, timeout: Duration) -> Result<Changes, String> {
+        let root = &self.stream.root;
+        let canonical_root = &self.stream.canonical_root;
+
+        // FSEvents reports paths with symbolic links resolved, so they are mapped back to
+        // paths below the root that was asked for
+        collect_changes(&self.stream.receiver, timeout, |path| {
+            let relative = std::path::Path::new(path.trim_end_matches('/'))
+                .strip_prefix(canonical_root)
+                .ok()?;
+            Some(join_path(root, relative))
+        })
+    }
+}
+
+#[cfg(windows)]
+impl ChangeNotifier {
+    pub fn new(root: &str) -> Result<ChangeNotifier, String> {
+        Ok(ChangeNotifier {
+            changes: directory_changes::DirectoryChanges::new(root)?,
+        })
+    }
+
+    /// ReadDirectoryChangesW watches the whole tree below the root, so there is nothing to update
+    pub fn sync_directories(&mut self, _directories: &[String]) -> Result<(), String> {
+        Ok(())
+    }
+
+    /// Wait until something changes or the timeout passes
+    pub fn wait(&mut self, timeout: Duration) -> Result<Changes, String> {
+        let root = &self.changes.root;
+
+        // Names are relative to the root and include the changed entry itself
+        collect_changes(&self.changes.receiver, timeout, |name| {
+            let parent = std::path::Path::new(name).parent()?;
+            Some(join_path(root, parent))
+        })
+    }
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "macos", windows)))]
+impl ChangeNotifier {
+    pub fn new(_root: &str) -> Result<ChangeNotifier, String> {
+        Err("file system notifications are not supported on this platform".to_string())
+    }
+
+    pub fn sync_directories(&mut self, _directories: &[String]) -> Result<(), String> {
+        Ok(())
+    }
+
+    pub fn wait(&mut self, _timeout: Duration) -> Result<Changes, String> {
+        Ok(Changes::Unknown)
+    }
+}
+
+/// Join a relative path onto the root the same way the scanned tree names its directories
+#[cfg(any(target_os = "macos", windows))]
+fn join_path(root: &str, relative: &std::path::Path) -> String {
+    if relative.as_os_str().is_empty() {
+        root.to_string()
+    } else {
+        std::path::Path::new(root)
+            .join(relative)
+            .to_string_lossy()
+            .to_string()
+    }
+}
+
+/// Wait for the first event, then take every event that already arrived. A `None` event
+/// means events were lost, and a path that can not be mapped to a directory is treated the
+/// same way
+#[cfg(any(target_os = "macos", windows))]
+fn collect_changes(
+    receiver: &std::sync::mpsc::Receiver<Option<String>>,
+    timeout: Duration,
+    to_directory: impl Fn(&str) -> Option<String>,
+) -> Result<Changes, String> {
+    use std::sync::mpsc::{RecvTimeoutError, TryRecvError};
+
+    let mut directories = HashSet::new();
+    let mut event = match receiver.recv_timeout(timeout) {
+        Ok(event) => event,
+        Err(RecvTimeoutError::Timeout) => return Ok(Changes::Directories(directories)),
+        Err(RecvTimeoutError::Disconnected) => {
+            return Err("stopped receiving file system changes".to_string())
+        }
+    };
+
+    loop {
+        match event.as_deref().and_then(&to_directory) {
+            Some(directory) => directories.insert(directory),
+            None => return Ok(Changes::Unknown),
+        };
+
+        event = match receiver.try_recv() {
+            Ok(event) => event,
+            Err(TryRecvError::Empty) => return Ok(Changes::Directories(directories)),
+            Err(TryRecvError::Disconnected) => {
+                return Err("stopped receiving file system changes".to_string())
+            }
+        };
+    }
+}
+
+#[cfg(target_os = "linux")]
+mod inotify {
+    use super::Changes;
+    use std::collections::{HashMap, HashSet};
+    use std::ffi::{c_char, c_int, c_short, c_ulong, CString};
+    use std::fs::File;
+    use std::io::{self, Read};
+    use std::os::fd::{AsRawFd, FromRawFd};
+    use std::time::Duration;
+
+    const IN_NONBLOCK: c_int = 0o4000;
+    const IN_CLOEXEC: c_int = 0o2000000;
+    const IN_MODIFY: u32 = 0x0002;
+    const IN_ATTRIB: u32 = 0x0004;
+    const IN_MOVED_FROM: u32 = 0x0040;
+    const IN_MOVED_TO: u32 = 0x0080;
+    const IN_CREATE: u32 = 0x0100;
+    const IN_DELETE: u32 = 0x0200;
+    const IN_Q_OVERFLOW: u32 = 0x4000;
+    const IN_IGNORED: u32 = 0x8000;
+    const IN_ONLYDIR: u32 = 0x0100_0000;
+    const POLLIN: c_short = 0x0001;
+    const ENOSPC: i32 = 28;
+
+    /// Size of the fixed part of an event, followed by a padded name
+    const EVENT_HEADER_LENGTH: usize = 16;
+
+    #[repr(C)]
+    struct PollFd {
+        fd: c_int,
+        events: c_short,
+        revents: c_short,
+    }
+
+    extern "C" {
+        fn inotify_init1(flags: c_int) -> c_int;
+        fn inotify_add_watch(fd: c_int, path: *const c_char, mask: u32) -> c_int;
+        fn inotify_rm_watch(fd: c_int, watch: c_int) -> c_int;
+        fn poll(fds: *mut PollFd, count: c_ulong, timeout: c_int) -> c_int;
+    }
+
+    pub struct Inotify {
+        file: File,
+        /// Path of the directory behind each watch descriptor
+        directories: HashMap<c_int, String>,
+        /// Directories watched since the last wait, entries added before the watch was in
+        /// place would otherwise be missed
+        pending: HashSet<String>,
+    }
+
+    impl Inotify {
+        pub fn new() -> Result<Inotify, String> {
+            let fd = unsafe { inotify_init1(IN_NONBLOCK | IN_CLOEXEC) };
+            if fd < 0 {
+                return Err(format!(
+                    "failed to start inotify: {}",
+                    io::Error::last_os_error()
+                ));
+            }
+
+            Ok(Inotify {
+                file: unsafe { File::from_raw_fd(fd) },
+                directories: HashMap::new(),
+                pending: HashSet::new(),
+            })
+        }
+
+        pub fn sync_directories(&mut self, directories: &[String]) -> Result<(), String> {
+            let wanted: HashSet<&String> = directories.iter().collect();
+            let fd = self.file.as_raw_fd();
+
+            // Directories that were removed or renamed are no longer in the tree
+            self.directories.retain(|&watch, path| {
+                let keep = wanted.contains(path);
+                if !keep {
+                    unsafe { inotify_rm_watch(fd, watch) };
+                }
+                keep
+            });
+
+            let watched: HashSet<String> = self.directories.values().cloned().collect();
+            for path in directories {
+                if watched.contains(path) {
+                    continue;
+                }
+
+                let c_path = match CString::new(path.as_str()) {
+                    Ok(c_path) => c_path,
+                    Err(_) => continue,
+                };
+                let mask = IN_MODIFY
+                    | IN_ATTRIB
+                    | IN_MOVED_FROM
+                    | IN_MOVED_TO
+                    | IN_CREATE
+                    | IN_DELETE
+                    | IN_ONLYDIR;
+                let watch = unsafe { inotify_add_watch(fd, c_path.as_ptr(), mask) };
+
+                if watch < 0 {
+                    let error = io::Error::last_os_error();
+                    // Directories that vanished or can not be read are skipped, running out
+                    // of watches means changes would go unnoticed
+                    if error.raw_os_error() == Some(ENOSPC) {
+                        return Err(format!("failed to watch {}: {}", path, error));
+                    }
+                    continue;
+                }
+
+                self.directories.insert(watch, path.clone());
+                self.pending.insert(path.clone());
+            }
+
+            Ok(())
+        }
+
+        pub fn wait(&mut self, timeout: Duration) -> Result<Changes, String> {
+            let mut directories: HashSet<String> = self.pending.drain().collect();
+
+            if directories.is_empty() {
+                let mut poll_fd = PollFd {
+                    fd: self.file.as_raw_fd(),
+                    events: POLLIN,
+                    revents: 0,
+                };
+                let timeout = timeout.as_millis().min(c_int::MAX as u128) as c_int;
+                if unsafe { poll(&mut poll_fd, 1, timeout) } < 0 {
+                    let error = io::Error::last_os_error();
+                    if error.kind() != io::ErrorKind::Interrupted {
+                        return Err(format!("failed to wait for inotify: {}", error));
+                    }
+                }
+            }
+
+            let mut buffer = vec![0u8; 64 * 1024];
+            loop {
+                let length = match self.file.read(&mut buffer) {
+                    Ok(length) => length,
+                    Err(e) if e.kind() == io::ErrorKind::WouldBlock => break,
+                    Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+                    Err(e) => return Err(format!("failed to read inotify events: {}", e)),
+                };
+
+                let mut offset = 0;
+                while offset + EVENT_HEADER_LENGTH <= length {
+                    let field = |index: usize| {
+                        let start = offset + index * 4;
+                        [
+                            buffer[start],
+                            buffer[start + 1],
+                            buffer[start + 2],
+                            buffer[start + 3],
+                        ]
+                    };
+                    let watch = c_int::from_ne_bytes(field(0));
+                    let mask = u32::from_ne_bytes(field(1));
+                    let name_length = u32::from_ne_bytes(field(3)) as usize;
+                    offset += EVENT_HEADER_LENGTH + name_length;
+
+                    if mask & IN_Q_OVERFLOW != 0 {
+                        return Ok(Changes::Unknown);
+                    }
+
+                    if mask & IN_IGNORED != 0 {
+                        self.directories.remove(&watch);
+                    } else if let Some(path) = self.directories.get(&watch) {
+                        directories.insert(path.clone());
+                    }
+                }
+            }
+
+            Ok(Changes::Directories(directories))
+        }
+    }
+}
+
+#[cfg(target_os = "macos")]
+mod fsevents {
+    use std::ffi::{c_char, c_void, CStr};
+    use std::path::PathBuf;
+    use std::sync::mpsc::{channel, Receiver, Sender};
+
+    type CFIndex = isize;
+    type Callback =
+        extern "C" fn(*const c_void, *mut c_void, usize, *mut c_void, *const u32, *const u64);
+
+    const CF_STRING_ENCODING_UTF8: u32 = 0x0800_0100;
+    const EVENT_ID_SINCE_NOW: u64 = u64::MAX;
+    const CREATE_FLAG_NO_DEFER: u32 = 0x0002;
+    const CREATE_FLAG_WATCH_ROOT: u32 = 0x0004;
+    const EVENT_FLAG_MUST_SCAN_SUB_DIRS: u32 = 0x0001;
+    const EVENT_FLAG_ROOT_CHANGED: u32 = 0x0020;
+    /// Seconds FSEvents waits to merge events before reporting them
+    const LATENCY: f64 = 0.1;
+
+    #[repr(C)]
+    struct StreamContext {
+        version: CFIndex,
+        info: *mut c_void,
+        retain: *const c_void,
+        release: *const c_void,
+        copy_description: *const c_void,
+    }
+
+    #[link(name = "CoreFoundation", kind = "framework")]
+    extern "C" {
+        static kCFTypeArrayCallBacks: c_void;
+        fn CFStringCreateWithBytes(
+            allocator: *const c_void,
+            bytes: *const u8,
+            length: CFIndex,
+            encoding: u32,
+            is_external: u8,
+        ) -> *const c_void;
+        fn CFArrayCreate(
+            allocator: *const c_void,
+            values: *const *const c_void,
+            count: CFIndex,
+            callbacks: *const c_void,
+        ) -> *const c_void;
+        fn CFRelease(object: *const c_void);
+    }
+
+    #[link(name = "CoreServices", kind = "framework")]
+    extern "C" {
+        fn FSEventStreamCreate(
+            allocator: *const c_void,
+            callback: Callback,
+            context: *const StreamContext,
+            paths: *const c_void,
+            since_when: u64,
+            latency: f64,
+            flags: u32,
+        ) -> *mut c_void;
+        fn FSEventStreamSetDispatchQueue(stream: *mut c_void, queue: *mut c_void);
+        fn FSEventStreamStart(stream: *mut c_void) -> u8;
+        fn FSEventStreamStop(stream: *mut c_void);
+        fn FSEventStreamInvalidate(stream: *mut c_void);
+        fn FSEventStreamRelease(stream: *mut c_void);
+    }
+
+    extern "C" {
+        fn dispatch_queue_create(label: *const c_char, attributes: *const c_void) -> *mut c_void;
+        fn dispatch_release(object: *mut c_void);
+    }
+
+    pub struct EventStream {
+        pub root: String,
+        pub canonical_root: PathBuf,
+        pub receiver: Receiver<Option<String>>,
+        stream: *mut c_void,
+        queue: *mut c_void,
+        sender: *mut Sender<Option<String>>,
+    }
+
+    /// Called on the dispatch queue with the directories that changed
+    extern "C" fn handle_events(
+        _stream: *const c_void,
+        info: *mut c_void,
+        count: usize,
+        paths: *mut c_void,
+        flags: *const u32,
+        _ids: *const u64,
+    ) {
+        let sender = unsafe { &*(info as *const Sender<Option<String>>) };
+        let paths = paths as *const *const c_char;
+
+        for i in 0..count {
+            let flag = unsafe { *flags.add(i) };
+            let event = if flag & (EVENT_FLAG_MUST_SCAN_SUB_DIRS | EVENT_FLAG_ROOT_CHANGED) != 0 {
+                None
+            } else {
+                let path = unsafe { CStr::from_ptr(*paths.add(i)) };
+                Some(path.to_string_lossy().to_string())
+            };
+            let _ = sender.send(event);
+        }
+    }
+
+    impl EventStream {
+        pub fn new(root: &str) -> Result<EventStream, String> {
+            let canonical_root = std::fs::canonicalize(root)
+                .map_err(|e| format!("failed to resolve {}: {}", root, e))?;
+            let canonical_string = canonical_root.to_string_lossy().to_string();
+            let (sender, receiver) = channel();
+            let sender = Box::into_raw(Box::new(sender));
+
+            unsafe {
+                let path = CFStringCreateWithBytes(
+                    std::ptr::null(),
+                    canonical_string.as_ptr(),
+                    canonical_string.len() as CFIndex,
+                    CF_STRING_ENCODING_UTF8,
+                    0,
+                );
+                let paths = CFArrayCreate(
+                    std::ptr::null(),
+                    &path,
+                    1,
+                    &kCFTypeArrayCallBacks as *const c_void,
+                );
+                let context = StreamContext {
+                    version: 0,
+                    info: sender as *mut c_void,
+                    retain: std::ptr::null(),
+                    release: std::ptr::null(),
+                    copy_description: std::ptr::null(),
+                };
+
+                let stream = FSEventStreamCreate(
+                    std::ptr::null(),
+                    handle_events,
+                    &context,
+                    paths,
+                    EVENT_ID_SINCE_NOW,
+                    LATENCY,
+                    CREATE_FLAG_NO_DEFER | CREATE_FLAG_WATCH_ROOT,
+                );
+                CFRelease(paths);
+                CFRelease(path);
+
+                if stream.is_null() {
+                    drop(Box::from_raw(sender));
+                    return Err("failed to create an FSEvents stream".to_string());
+                }
+
+                let queue = dispatch_queue_create(c"fmap.watch".as_ptr(), std::ptr::null());
+                FSEventStreamSetDispatchQueue(stream, queue);
+
+                let event_stream = EventStream {
+                    root: root.to_string(),
+                    canonical_root,
+                    receiver,
+                    stream,
+                    queue,
+                    sender,
+                };
+
+                if FSEventStreamStart(stream) == 0 {
+                    return Err("failed to start the FSEvents stream".to_string());
+                }
+
+                Ok(event_stream)
+            }
+        }
+    }
+
+    impl Drop for EventStream {
+        fn drop(&mut self) {
+            unsafe {
+                FSEventStreamStop(self.stream);
+                FSEventStreamInvalidate(self.stream);
+                FSEventStreamRelease(self.stream);
+                dispatch_release(self.queue);
+                drop(Box::from_raw(self.sender));
+            }
+        }
+    }
+}
+
+#[cfg(windows)]
+mod directory_changes {
+    use std::ffi::c_void;
+    use std::sync::mpsc::{channel, Receiver, Sender};
+    use std::thread;
+
+    type Handle = *mut c_void;
+
+    const FILE_LIST_DIRECTORY: u32 = 0x0001;
+    const FILE_SHARE_READ: u32 = 0x0001;
+    const FILE_SHARE_WRITE: u32 = 0x0002;
+    const FILE_SHARE_DELETE: u32 = 0x0004;
+    const OPEN_EXISTING: u32 = 3;
+    const FILE_FLAG_BACKUP_SEMANTICS: u32 = 0x0200_0000;
+    const FILE_NOTIFY_CHANGE_FILE_NAME: u32 = 0x0001;
+    const FILE_NOTIFY_CHANGE_DIR_NAME: u32 = 0x0002;
+    const FILE_NOTIFY_CHANGE_SIZE: u32 = 0x0008;
+    const FILE_NOTIFY_CHANGE_LAST_WRITE: u32 = 0x0010;
+    const INVALID_HANDLE_VALUE: Handle = -1isize as Handle;
+
+    /// Size of the fixed part of a FILE_NOTIFY_INFORMATION, followed by the name
+    const NOTIFY_HEADER_LENGTH: usize = 12;
+
+    extern "system" {
+        fn CreateFileW(
+            name: *const u16,
+            access: u32,
+            share_mode: u32,
+            security_attributes: *const c_void,
+            creation_disposition: u32,
+            flags: u32,
+            template: Handle,
+        ) -> Handle;
+        fn ReadDirectoryChangesW(
+            directory: Handle,
+            buffer: *mut c_void,
+            buffer_length: u32,
+            watch_subtree: i32,
+            filter: u32,
+            bytes_returned: *mut u32,
+            overlapped: *mut c_void,
+            completion_routine: *const c_void,
+        ) -> i32;
+        fn CloseHandle(object: Handle) -> i32;
+    }
+
+    pub struct DirectoryChanges {
+        pub root: String,
+        pub receiver: Receiver<Option<String>>,
+    }
+
+    impl DirectoryChanges {
+        pub fn new(root: &str) -> Result<DirectoryChanges, String> {
+            let name: Vec<u16> = root.encode_utf16().chain(Some(0)).collect();
+            let directory = unsafe {
+                CreateFileW(
+                    name.as_ptr(),
+                    FILE_LIST_DIRECTORY,
+                    FILE_SHARE_READ | FILE_SHARE_WRITE | FILE_SHARE_DELETE,
+                    std::ptr::null(),
+                    OPEN_EXISTING,
+                    FILE_FLAG_BACKUP_SEMANTICS,
+                    std::ptr::null_mut(),
+                )
+            };
+            if directory == INVALID_HANDLE_VALUE {
+                return Err(format!(
+                    "failed to open {}: {}",
+                    root,
+                    std::io::Error::last_os_error()
+                ));
+            }
+
+            let (sender, receiver) = channel();
+            // The handle is passed as an integer since raw pointers can not be sent to a thread
+            let handle = directory as usize;
+
+            // ReadDirectoryChangesW blocks until something changes, so it runs on its own thread
+            // which stops at the first change after the receiver is dropped
+            thread::spawn(move || {
+                read_changes(handle as Handle, &sender);
+                unsafe { CloseHandle(handle as Handle) };
+            });
+
+            Ok(DirectoryChanges {
+                root: root.to_string(),
+                receiver,
+            })
+        }
+    }
+
+    fn read_changes(directory: Handle, sender: &Sender<Option<String>>) {
+        // The buffer has to be aligned to four bytes
+        let mut buffer = vec![0u32; 16 * 1024];
+        loop {
+            let mut length = 0;
+            let result = unsafe {
+                ReadDirectoryChangesW(
+                    directory,
+                    buffer.as_mut_ptr() as *mut c_void,
+                    (buffer.len() * 4) as u32,
+                    1,
+                    FILE_NOTIFY_CHANGE_FILE_NAME
+                        | FILE_NOTIFY_CHANGE_DIR_NAME
+                        | FILE_NOTIFY_CHANGE_SIZE
+                        | FILE_NOTIFY_CHANGE_LAST_WRITE,
+                    &mut length,
+                    std::ptr::null_mut(),
+                    std::ptr::null(),
+                )
+            };
+            if result == 0 {
+                return;
+            }
+
+            // A length of zero means the buffer overflowed and events were lost
+            if length == 0 {
+                if sender.send(None).is_err() {
+                    return;
+                }
+                continue;
+            }
+
+            let bytes = unsafe {
+                std::slice::from_raw_parts(buffer.as_ptr() as *const u8, length as usize)
+            };
+            let mut offset = 0;
+            loop {
+                let field = |index: usize| {
+                    let start = offset + index * 4;
+                    u32::from_ne_bytes([
+                        bytes[start],
+                        bytes[start + 1],
+                        bytes[start + 2],
+                        bytes[start + 3],
+                    ]) as usize
+                };
+                let next_offset = field(0);
+                let name_length = field(2);
+                let name_start = offset + NOTIFY_HEADER_LENGTH;
+                let name: Vec<u16> = bytes[name_start..name_start + name_length]
+                    .chunks_exact(2)
+                    .map(|pair| u16::from_ne_bytes([pair[0], pair[1]]))
+                    .collect();
+
+                if sender.send(Some(String::from_utf16_lossy(&name))).is_err() {
+                    return;
+                }
+
+                if next_offset == 0 {
+                    break;
+                }
+                offset += next_offset;
+            }
+        }
+    }
+}
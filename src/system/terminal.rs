@@ -0,0 +1,276 @@
+use std::env;
+use std::io::{self, Read};
+
+/// Puts the terminal into raw mode while alive, the previous mode is restored when dropped
+pub struct RawMode {
+    #[cfg(not(windows))]
+    saved_settings: String,
+    #[cfg(windows)]
+    saved_modes: (u32, u32),
+}
+
+#[cfg(not(windows))]
+pub fn enable_raw_mode() -> Result<RawMode, String> {
+    use std::process::{Command, Stdio};
+
+    let output = Command::new("stty")
+        .arg("-g")
+        .stdin(Stdio::inherit())
+        .output()
+        .map_err(|e| format!("failed to run stty: {}", e))?;
+
+    if !output.status.success() {
+        return Err("stdin is not a terminal".to_string());
+    }
+
+    let saved_settings = String::from_utf8_lossy(&output.stdout).trim().to_string();
+
+    // min 0 and time 1 make reads return after a tenth of a second without input,
+    // which is needed to tell the escape key apart from an escape sequence
+    let status = Command::new("stty")
+        .args(["raw", "-echo", "min", "0", "time", "1"])
+        .stdin(Stdio::inherit())
+        .status()
+        .map_err(|e| format!("failed to run stty: {}", e))?;
+
+    if !status.success() {
+        return Err("failed to put the terminal into raw mode".to_string());
+    }
+
+    Ok(RawMode { saved_settings })
+}
+
+#[cfg(not(windows))]
+impl Drop for RawMode {
+    fn drop(&mut self) {
+        use std::process::{Command, Stdio};
+
+        let _ = Command::new("stty")
+            .arg(&self.saved_settings)
+            .stdin(Stdio::inherit())
+            .status();
+    }
+}
+
+#[cfg(windows)]
+mod console {
+    pub type Handle = *mut std::ffi::c_void;
+
+    pub const STD_INPUT_HANDLE: u32 = -10i32 as u32;
+    pub const STD_OUTPUT_HANDLE: u32 = -11i32 as u32;
+    pub const STD_ERROR_HANDLE: u32 = -12i32 as u32;
+    pub const ENABLE_PROCESSED_INPUT: u32 = 0x0001;
+    pub const ENABLE_LINE_INPUT: u32 = 0x0002;
+    pub const ENABLE_ECHO_INPUT: u32 = 0x0004;
+    pub const ENABLE_VIRTUAL_TERMINAL_INPUT: u32 = 0x0200;
+    pub const ENABLE_VIRTUAL_TERMINAL_PROCESSING: u32 = 0x0004;
+
+    #[repr(C)]
+    pub struct Coord {
+        pub x: i16,
+        pub y: i16,
+    }
+
+    #[repr(C)]
+    pub struct SmallRect {
+        pub left: i16,
+        pub top: i16,
+        pub right: i16,
+        pub bottom: i16,
+    }
+
+    #[repr(C)]
+    pub struct ScreenBufferInfo {
+        pub size: Coord,
+        pub cursor_position: Coord,
+        pub attributes: u16,
+        pub window: SmallRect,
+        pub maximum_window_size: Coord,
+    }
+
+    extern "system" {
+        pub fn GetStdHandle(std_handle: u32) -> Handle;
+        pub fn GetConsoleMode(console: Handle, mode: *mut u32) -> i32;
+        pub fn SetConsoleMode(console: Handle, mode: u32) -> i32;
+        pub fn GetConsoleScreenBufferInfo(console: Handle, info: *mut ScreenBufferInfo) -> i32;
+    }
+}
+
+/// Interactive views draw on stderr so stdout stays free for their result
+#[cfg(windows)]
+pub fn enable_raw_mode() -> Result<RawMode, String> {
+    use console::*;
+
+    unsafe {
+        let input = GetStdHandle(STD_INPUT_HANDLE);
+        let output = GetStdHandle(STD_ERROR_HANDLE);
+        let mut input_mode = 0;
+        let mut output_mode = 0;
+
+        if GetConsoleMode(input, &mut input_mode) == 0 {
+            return Err("stdin is not a terminal".to_string());
+        }
+        if GetConsoleMode(output, &mut output_mode) == 0 {
+            return Err("stderr is not a terminal".to_string());
+        }
+
+        let raw_input_mode = (input_mode
+            & !(ENABLE_PROCESSED_INPUT | ENABLE_LINE_INPUT | ENABLE_ECHO_INPUT))
+            | ENABLE_VIRTUAL_TERMINAL_INPUT;
+
+        if SetConsoleMode(input, raw_input_mode) == 0
+            || SetConsoleMode(output, output_mode | ENABLE_VIRTUAL_TERMINAL_PROCESSING) == 0
+        {
+            return Err("failed to put the terminal into raw mode".to_string());
+        }
+
+        Ok(RawMode {
+            saved_modes: (input_mode, output_mode),
+        })
+    }
+}
+
+#[cfg(windows)]
+impl Drop for RawMode {
+    fn drop(&mut self) {
+        use console::*;
+
+        unsafe {
+            SetConsoleMode(GetStdHandle(STD_INPUT_HANDLE), self.saved_modes.0);
+            SetConsoleMode(GetStdHandle(STD_ERROR_HANDLE), self.saved_modes.1);
+        }
+    }
+}
+
+/// Size of the terminal as (columns, rows), falls back to the COLUMNS and LINES variables
+pub fn terminal_size() -> Option<(usize, usize)> {
+    query_terminal_size().or_else(|| {
+        let columns = env::var("COLUMNS").ok()?.parse().ok()?;
+        let rows = env::var("LINES").ok()?.parse().ok()?;
+        Some((columns, rows))
+    })
+}
+
+#[cfg(not(windows))]
+fn query_terminal_size() -> Option<(usize, usize)> {
+    use std::process::{Command, Stdio};
+
+    let output = Command::new("stty")
+        .arg("size")
+        .stdin(Stdio::inherit())
+        .stderr(Stdio::null())
+        .output()
+        .ok()?;
+
+    let size = String::from_utf8_lossy(&output.stdout);
+    let mut parts = size.split_whitespace();
+    let rows = parts.next()?.parse().ok()?;
+    let columns = parts.next()?.parse().ok()?;
+
+    Some((columns, rows))
+}
+
+#[cfg(windows)]
+fn query_terminal_size() -> Option<(usize, usize)> {
+    use console::*;
+
+    unsafe {
+        // Interactive views draw on stderr, but stdout is still a console when only stderr
+        // is redirected
+        let mut info: ScreenBufferInfo = std::mem::zeroed();
+        let found = [STD_ERROR_HANDLE, STD_OUTPUT_HANDLE]
+            .iter()
+            .any(|&handle| GetConsoleScreenBufferInfo(GetStdHandle(handle), &mut info) != 0);
+        if !found {
+            return None;
+        }
+
+        let columns = (info.window.right - info.window.left + 1) as usize;
+        let rows = (info.window.bottom - info.window.top + 1) as usize;
+        Some((columns, rows))
+    }
+}
+
+pub enum Key {
+    Up,
+    Down,
+    Left,
+    Right,
+    PageUp,
+    PageDown,
+    Home,
+    End,
+    Enter,
+    Escape,
+    Backspace,
+    /// ctrl+c, which raw mode delivers as a key instead of a signal
+    Interrupt,
+    Char(char),
+}
+
+/// Read a single key press from a terminal in raw mode, returns none if no key was read
+pub fn read_key(input: &mut impl Read) -> io::Result<Option<Key>> {
+    let mut buffer = [0u8; 1];
+    if input.read(&mut buffer)? == 0 {
+        return Ok(None);
+    }
+
+    let key = match buffer[0] {
+        b'\r' | b'\n' => Key::Enter,
+        0x7f | 0x08 => Key::Backspace,
+        0x03 => Key::Interrupt,
+        0x1b => {
+            let mut sequence = [0u8; 1];
+            if input.read(&mut sequence)? == 0 || (sequence[0] != b'[' && sequence[0] != b'O') {
+                return Ok(Some(Key::Escape));
+            }
+
+            let mut code = Vec::new();
+            loop {
+                if input.read(&mut sequence)? == 0 {
+                    return Ok(Some(Key::Escape));
+                }
+                code.push(sequence[0]);
+                if sequence[0].is_ascii_alphabetic() || sequence[0] == b'~' {
+                    break;
+                }
+            }
+
+            match code.as_slice() {
+                b"A" => Key::Up,
+                b"B" => Key::Down,
+                b"C" => Key::Right,
+                b"D" => Key::Left,
+                b"H" | b"1~" => Key::Home,
+                b"F" | b"4~" => Key::End,
+                b"5~" => Key::PageUp,
+                b"6~" => Key::PageDown,
+                _ => return Ok(None),
+            }
+        }
+        byte => {
+            // Collect the rest of a multi-byte UTF-8 character
+            let length = match byte {
+                0xc0..=0xdf => 2,
+                0xe0..=0xef => 3,
+                0xf0..=0xf7 => 4,
+                _ => 1,
+            };
+            let mut bytes = vec![byte];
+            let mut next = [0u8; 1];
+            while bytes.len() < length {
+                // Reads time out after a tenth of a second in raw mode, the rest of the
+                // character can arrive later than that
+                if input.read(&mut next)? == 1 {
+                    bytes.push(next[0]);
+                }
+            }
+            match String::from_utf8(bytes).ok().and_then(|s| s.chars().next()) {
+                Some(c) if !c.is_control() => Key::Char(c),
+                _ => return Ok(None),
+            }
+        }
+    };
+
+    Ok(Some(key))
+}
@@ -0,0 +1,229 @@
+use crate::colors::{ExtensionGroup, LsColors};
+use crate::size::parse_size;
+use crate::styles::{Color, TextStyle};
+use crate::system::config::{get_optional_setting_from_config, ConfigOption};
+use crate::OS;
+
+pub const DEFAULT_THEME: &str = "dark";
+pub const THEME_NAMES: [&str; 4] = ["dark", "light", "solarized", "monochrome"];
+
+/*
+ * Themes are lists of key=style pairs, the same format as the theme-styles config setting
+ * - keys: a kind of entry like directory or executable, an extension group like archive,
+ *   an extension like .rs, a size threshold like >1GB, or size, count and connector
+ * - styles: colors like red, 208 or #ff8800, text styles like bold, bg:<color> for the
+ *   background, or none, separated by spaces
+ */
+const DARK: &[(&str, &str)] = &[("size", "cyan"), ("count", "yellow"), ("connector", "dim")];
+const LIGHT: &[(&str, &str)] = &[
+    ("directory", "bold blue"),
+    ("symlink", "cyan"),
+    ("orphan", "bold red"),
+    ("executable", "green"),
+    ("pipe", "yellow"),
+    ("socket", "purple"),
+    ("device", "bold yellow"),
+    ("archive", "red"),
+    ("image", "purple"),
+    ("audio", "cyan"),
+    ("size", "blue"),
+    ("count", "purple"),
+    ("connector", "bright-black"),
+    (">1GB", "bold red"),
+];
+const SOLARIZED: &[(&str, &str)] = &[
+    ("directory", "bold #268bd2"),
+    ("symlink", "#2aa198"),
+    ("orphan", "#dc322f"),
+    ("executable", "#859900"),
+    ("pipe", "#b58900"),
+    ("socket", "#d33682"),
+    ("device", "#cb4b16"),
+    ("archive", "#dc322f"),
+    ("image", "#d33682"),
+    ("audio", "#6c71c4"),
+    ("size", "#2aa198"),
+    ("count", "#b58900"),
+    ("connector", "#586e75"),
+    (">100MB", "#cb4b16"),
+    (">1GB", "bold #dc322f"),
+];
+const MONOCHROME: &[(&str, &str)] = &[
+    ("directory", "bold"),
+    ("symlink", "italic"),
+    ("orphan", "strikethrough"),
+    ("executable", "underline"),
+    ("connector", "dim"),
+    (">1GB", "bold"),
+];
+
+/// Styles of the tree output, each style is a list of SGR codes like 01;34 as in LS_COLORS
+pub struct Theme {
+    /// Styles of names, by kind of entry and by extension
+    pub names: LsColors,
+    pub size: Option<String>,
+    /// Styles of sizes of at least a number of bytes, largest first
+    pub size_thresholds: Vec<(u64, String)>,
+    pub count: Option<String>,
+    pub connector: Option<String>,
+}
+
+impl Theme {
+    /// One of the built in themes, the dark theme uses LS_COLORS for names
+    pub fn named(name: &str) -> Option<Theme> {
+        let (names, styles) = match name {
+            "dark" => (LsColors::from_env(), DARK),
+            "light" => (LsColors::empty(), LIGHT),
+            "solarized" => (LsColors::empty(), SOLARIZED),
+            "monochrome" => (LsColors::empty(), MONOCHROME),
+            _ => return None,
+        };
+
+        let mut theme = Theme {
+            names,
+            size: None,
+            size_thresholds: Vec::new(),
+            count: None,
+            connector: None,
+        };
+        for (key, style) in styles {
+            theme
+                .set_style(key, style)
+                .expect("Built in themes should be valid");
+        }
+
+        Some(theme)
+    }
+
+    /// Load the theme passed with --theme or set in the config, with the theme-styles
+    /// setting of the config applied on top
+    pub fn load(name: Option<String>, os: &OS) -> Result<Theme, String> {
+        let name = name
+            .or_else(|| {
+                get_optional_setting_from_config(ConfigOption::Theme, os)
+                    .map(|setting| setting.value)
+                    .filter(|value| !value.is_empty() && value != "None")
+            })
+            .unwrap_or(DEFAULT_THEME.to_string());
+
+        let mut theme = Theme::named(&name)
+            .ok_or(format!("theme must be one of {}", THEME_NAMES.join(", ")))?;
+
+        if let Some(setting) = get_optional_setting_from_config(ConfigOption::ThemeStyles, os) {
+            theme
+                .set_styles(&setting.value)
+                .map_err(|e| format!("invalid theme-styles setting: {}", e))?;
+        }
+
+        Ok(theme)
+    }
+
+    /// Apply a comma separated list of key=style pairs
+    pub fn set_styles(&mut self, list: &str) -> Result<(), String> {
+        for pair in list.split(',').map(|pair| pair.trim()) {
+            if pair.is_empty() || pair == "None" {
+                continue;
+            }
+
+            match pair.split_once('=') {
+                Some((key, style)) => self.set_style(key.trim(), style)?,
+                None => return Err(format!("expected key=style, found \"{}\"", pair)),
+            }
+        }
+
+        Ok(())
+    }
+
+    pub fn set_style(&mut self, key: &str, style: &str) -> Result<(), String> {
+        let style = parse_style(style)?;
+
+        if let Some(size) = key.strip_prefix('>') {
+            let size = parse_size(size).ok_or(format!("invalid size in \"{}\"", key))?;
+            self.size_thresholds
+                .retain(|(existing, _)| *existing != size);
+            self.size_thresholds.push((size, style));
+            self.size_thresholds.sort_by(|(a, _), (b, _)| b.cmp(a));
+            return Ok(());
+        }
+
+        let suffix = key.strip_prefix('*').unwrap_or(key);
+        if suffix.starts_with('.') {
+            self.names.set_suffix(suffix, &style);
+            return Ok(());
+        }
+
+        let style_option = Some(style.clone()).filter(|style| !style.is_empty());
+        match key {
+            "size" => self.size = style_option,
+            "count" => self.count = style_option,
+            "connector" => self.connector = style_option,
+            "archive" => self.names.set_group(ExtensionGroup::Archive, &style),
+            "image" => self.names.set_group(ExtensionGroup::ImageAndVideo, &style),
+            "audio" => self.names.set_group(ExtensionGroup::Audio, &style),
+            "device" => {
+                self.names.set_kind("bd", &style);
+                self.names.set_kind("cd", &style);
+            }
+            _ => {
+                let kind = kind_code(key).ok_or(format!("unknown theme key \"{}\"", key))?;
+                self.names.set_kind(kind, &style);
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Style of a size column, from the largest threshold the size reaches
+    pub fn size_style(&self, size: u64) -> Option<&str> {
+        self.size_thresholds
+            .iter()
+            .find(|(threshold, _)| size >= *threshold)
+            .map(|(_, style)| style.as_str())
+            .or(self.size.as_deref())
+    }
+}
+
+/// The two letter LS_COLORS code of a kind of entry
+fn kind_code(kind: &str) -> Option<&'static str> {
+    let code = match kind {
+        "directory" => "di",
+        "file" => "fi",
+        "symlink" => "ln",
+        "orphan" => "or",
+        "executable" => "ex",
+        "pipe" => "pi",
+        "socket" => "so",
+        "block-device" => "bd",
+        "char-device" => "cd",
+        "setuid" => "su",
+        "setgid" => "sg",
+        "sticky" => "st",
+        "other-writable" => "ow",
+        "sticky-other-writable" => "tw",
+        _ => return None,
+    };
+    Some(code)
+}
+
+/// Turn a style like "bold #ff8800 bg:black" into SGR codes, none is an empty style
+fn parse_style(style: &str) -> Result<String, String> {
+    let mut parameters = Vec::new();
+
+    for word in style.split_whitespace() {
+        if word == "none" {
+            continue;
+        }
+
+        if let Some(text_style) = TextStyle::parse(word) {
+            parameters.push(text_style.sgr_parameter().to_string());
+        } else if let Some(color) = word.strip_prefix("bg:").and_then(Color::parse) {
+            parameters.push(color.sgr_parameters(true));
+        } else if let Some(color) = Color::parse(word) {
+            parameters.push(color.sgr_parameters(false));
+        } else {
+            return Err(format!("unknown color or style \"{}\"", word));
+        }
+    }
+
+    Ok(parameters.join(";"))
+}
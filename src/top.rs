@@ -0,0 +1,140 @@
+use crate::json::JsonValue;
+use crate::scan::DirectoryObject;
+use crate::size::bytes_to_best_size;
+use crate::styles::TextStyle;
+use crate::utils::relative_path;
+
+pub struct TopEntry {
+    pub path: String,
+    pub size: u64,
+}
+
+pub struct TopReport {
+    pub total_size: u64,
+    pub files: Vec<TopEntry>,
+    pub directories: Vec<TopEntry>,
+}
+
+/// Find the largest files and directories in the tree, only counting files with one of
+/// the given extensions if any are given
+pub fn find_largest(tree: &DirectoryObject, count: usize, extensions: &[String]) -> TopReport {
+    let mut files = Vec::new();
+    let mut directories = Vec::new();
+    let extensions: Vec<String> = extensions.iter().map(|e| e.to_lowercase()).collect();
+    let total_size = collect_sizes(tree, tree, &extensions, &mut files, &mut directories);
+
+    files.sort_by(|a: &TopEntry, b: &TopEntry| b.size.cmp(&a.size).then(a.path.cmp(&b.path)));
+    files.truncate(count);
+    directories.sort_by(|a: &TopEntry, b: &TopEntry| b.size.cmp(&a.size).then(a.path.cmp(&b.path)));
+    directories.truncate(count);
+
+    TopReport {
+        total_size,
+        files,
+        directories,
+    }
+}
+
+fn collect_sizes(
+    root: &DirectoryObject,
+    directory: &DirectoryObject,
+    extensions: &[String],
+    files: &mut Vec<TopEntry>,
+    directories: &mut Vec<TopEntry>,
+) -> u64 {
+    let mut size = 0;
+
+    for file in &directory.files {
+        if !extensions.is_empty() && !extensions.contains(&file.ending.to_lowercase()) {
+            continue;
+        }
+
+        size += file.size;
+        files.push(TopEntry {
+            path: relative_path(&root.path, &file.path),
+            size: file.size,
+        });
+    }
+
+    for sub_directory in &directory.directories {
+        let sub_directory_size = collect_sizes(root, sub_directory, extensions, files, directories);
+        size += sub_directory_size;
+        if sub_directory_size > 0 {
+            directories.push(TopEntry {
+                path: relative_path(&root.path, &sub_directory.path),
+                size: sub_directory_size,
+            });
+        }
+    }
+
+    size
+}
+
+fn percentage_of(size: u64, total: u64) -> f64 {
+    if total == 0 {
+        0.0
+    } else {
+        size as f64 / total as f64 * 100.0
+    }
+}
+
+pub fn display_top(report: &TopReport) {
+    println!(
+        "{}Largest files{} (of {} total)",
+        TextStyle::Bold.to_ansi_style_string(),
+        TextStyle::Reset.to_ansi_style_string(),
+        bytes_to_best_size(report.total_size)
+    );
+    display_entries(&report.files, report.total_size);
+
+    println!();
+    println!(
+        "{}Largest directories{}",
+        TextStyle::Bold.to_ansi_style_string(),
+        TextStyle::Reset.to_ansi_style_string()
+    );
+    display_entries(&report.directories, report.total_size);
+}
+
+fn display_entries(entries: &[TopEntry], total_size: u64) {
+    if entries.is_empty() {
+        println!("    none");
+    }
+
+    let rank_width = entries.len().to_string().len();
+    for (i, entry) in entries.iter().enumerate() {
+        println!(
+            "  {:>rank_width$}. {:>11} {:>6.2}%  {}",
+            i + 1,
+            bytes_to_best_size(entry.size),
+            percentage_of(entry.size, total_size),
+            entry.path,
+        );
+    }
+}
+
+pub fn top_to_json(report: &TopReport) -> JsonValue {
+    JsonValue::object()
+        .with("total_size", report.total_size.into())
+        .with("files", entries_to_json(&report.files, report.total_size))
+        .with(
+            "directories",
+            entries_to_json(&report.directories, report.total_size),
+        )
+}
+
+fn entries_to_json(entries: &[TopEntry], total_size: u64) -> JsonValue {
+    entries
+        .iter()
+        .map(|entry| {
+            JsonValue::object()
+                .with("path", entry.path.as_str().into())
+                .with("size", entry.size.into())
+                .with(
+                    "percentage",
+                    ((percentage_of(entry.size, total_size) * 100.0).round() / 100.0).into(),
+                )
+        })
+        .collect::<Vec<JsonValue>>()
+        .into()
+}
@@ -0,0 +1,360 @@
+use crate::charset::percentage_bar;
+use crate::json::JsonValue;
+use crate::scan::{file_from_name, is_valid_entry_name, DirectoryObject};
+use crate::size::bytes_to_best_size;
+use crate::styles::{Color, TextStyle};
+use crate::system::terminal::{enable_raw_mode, read_key, terminal_size, Key};
+use std::fs;
+use std::io::{self, Write};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+const NCDU_MAJOR_VERSION: u64 = 1;
+const NCDU_MINOR_VERSION: u64 = 2;
+const BAR_WIDTH: usize = 10;
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum SortOrder {
+    Size,
+    Name,
+}
+
+struct UsageEntry<'a> {
+    name: String,
+    size: u64,
+    directory: Option<&'a DirectoryObject>,
+}
+
+fn get_entries(directory: &DirectoryObject, sort_order: SortOrder) -> Vec<UsageEntry<'_>> {
+    let mut entries: Vec<UsageEntry> = directory
+        .directories
+        .iter()
+        .map(|sub_directory| UsageEntry {
+            name: format!("/{}", sub_directory.base_name()),
+            size: sub_directory.total_size(),
+            directory: Some(sub_directory),
+        })
+        .chain(directory.files.iter().map(|file| UsageEntry {
+            name: file.full_name.clone(),
+            size: file.size,
+            directory: None,
+        }))
+        .collect();
+
+    match sort_order {
+        SortOrder::Size => entries.sort_by(|a, b| b.size.cmp(&a.size).then(a.name.cmp(&b.name))),
+        SortOrder::Name => entries.sort_by(|a, b| a.name.cmp(&b.name)),
+    }
+
+    entries
+}
+
+struct Explorer<'a> {
+    path: Vec<&'a DirectoryObject>,
+    cursors: Vec<usize>,
+    scroll: usize,
+    sort_order: SortOrder,
+}
+
+impl<'a> Explorer<'a> {
+    fn current(&self) -> &'a DirectoryObject {
+        self.path[self.path.len() - 1]
+    }
+
+    fn cursor(&mut self) -> &mut usize {
+        let last = self.cursors.len() - 1;
+        &mut self.cursors[last]
+    }
+
+    fn render(
+        &mut self,
+        (columns, lines): (usize, usize),
+        output: &mut impl Write,
+    ) -> io::Result<()> {
+        let visible_rows = lines.saturating_sub(3).max(1);
+        let directory = self.current();
+        let entries = get_entries(directory, self.sort_order);
+        let total = directory.total_size();
+        let cursor = *self.cursor();
+
+        if cursor < self.scroll {
+            self.scroll = cursor;
+        } else if cursor >= self.scroll + visible_rows {
+            self.scroll = cursor + 1 - visible_rows;
+        }
+
+        let mut screen = String::from("\u{001b}[H");
+        let header = format!("--- {} ---", directory.path);
+        screen.push_str(&format!(
+            "{}{}{}\u{001b}[K\r\n",
+            TextStyle::Bold.to_ansi_style_string(),
+            header.chars().take(columns).collect::<String>(),
+            TextStyle::Reset.to_ansi_style_string()
+        ));
+
+        for index in self.scroll..self.scroll + visible_rows {
+            if let Some(entry) = entries.get(index) {
+                let (bar, percentage) = percentage_bar(entry.size, total, BAR_WIDTH);
+                let line = format!(
+                    "{:>11} {:>5.1}% [{}] {}",
+                    bytes_to_best_size(entry.size),
+                    percentage,
+                    bar,
+                    entry.name
+                );
+                let style = if index == cursor {
+                    TextStyle::Invert.to_ansi_style_string()
+                } else if entry.directory.is_some() {
+                    Color::Blue.to_ansi_color_string()
+                } else {
+                    String::new()
+                };
+                screen.push_str(&format!(
+                    "{}{}{}",
+                    style,
+                    line.chars().take(columns).collect::<String>(),
+                    TextStyle::Reset.to_ansi_style_string()
+                ));
+            }
+            screen.push_str("\u{001b}[K\r\n");
+        }
+
+        screen.push_str("\u{001b}[K\r\n");
+        let footer = format!(
+            "Total: {}  Items: {}  |  enter: open  left: back  s/n: sort by size/name  q: quit",
+            bytes_to_best_size(total),
+            entries.len()
+        );
+        screen.push_str(&format!(
+            "{}{}{}\u{001b}[K",
+            TextStyle::Dim.to_ansi_style_string(),
+            footer.chars().take(columns).collect::<String>(),
+            TextStyle::Reset.to_ansi_style_string()
+        ));
+
+        output.write_all(screen.as_bytes())?;
+        output.flush()
+    }
+
+    fn go_to_parent(&mut self) {
+        if self.path.len() > 1 {
+            self.path.pop();
+            self.cursors.pop();
+            self.scroll = 0;
+        }
+    }
+
+    /// Handle a key press, returns true once the explorer should exit
+    fn handle_key(&mut self, key: Key, lines: usize) -> bool {
+        let entries = get_entries(self.current(), self.sort_order);
+        let last_entry = entries.len().saturating_sub(1);
+        let page_size = lines.saturating_sub(3).max(1);
+        let cursor = *self.cursor();
+
+        match key {
+            Key::Up | Key::Char('k') => *self.cursor() = cursor.saturating_sub(1),
+            Key::Down | Key::Char('j') => *self.cursor() = (cursor + 1).min(last_entry),
+            Key::PageUp => *self.cursor() = cursor.saturating_sub(page_size),
+            Key::PageDown => *self.cursor() = (cursor + page_size).min(last_entry),
+            Key::Home | Key::Char('g') => *self.cursor() = 0,
+            Key::End | Key::Char('G') => *self.cursor() = last_entry,
+            Key::Enter | Key::Right | Key::Char('l') => {
+                if let Some(directory) = entries.get(cursor).and_then(|entry| entry.directory) {
+                    self.path.push(directory);
+                    self.cursors.push(0);
+                    self.scroll = 0;
+                }
+            }
+            Key::Left | Key::Backspace | Key::Char('h') => self.go_to_parent(),
+            Key::Char('s') => self.sort_order = SortOrder::Size,
+            Key::Char('n') => self.sort_order = SortOrder::Name,
+            Key::Char('q') | Key::Escape | Key::Interrupt => return true,
+            _ => {}
+        }
+
+        false
+    }
+}
+
+/// Open an interactive explorer listing directories by their cumulative size
+pub fn explore_usage(tree: &DirectoryObject) -> Result<(), String> {
+    let raw_mode = enable_raw_mode()?;
+
+    let mut output = io::stderr();
+    let mut input = io::stdin().lock();
+    let mut explorer = Explorer {
+        path: vec![tree],
+        cursors: vec![0],
+        scroll: 0,
+        sort_order: SortOrder::Size,
+    };
+
+    let _ = output.write_all(b"\x1b[?1049h\x1b[?25l\x1b[2J");
+    let mut size = terminal_size().unwrap_or((80, 24));
+    let mut needs_redraw = true;
+
+    let result = loop {
+        if needs_redraw {
+            if let Err(e) = explorer.render(size, &mut output) {
+                break Err(format!("failed to draw explorer: {}", e));
+            }
+            needs_redraw = false;
+        }
+
+        match read_key(&mut input) {
+            Ok(Some(key)) => {
+                // Asking for the size runs stty, so it is only done once per key press
+                size = terminal_size().unwrap_or((80, 24));
+                if explorer.handle_key(key, size.1) {
+                    break Ok(());
+                }
+                needs_redraw = true;
+            }
+            Ok(None) => {}
+            Err(e) => break Err(format!("failed to read input: {}", e)),
+        }
+    };
+
+    let _ = output.write_all(b"\x1b[?25h\x1b[?1049l");
+    let _ = output.flush();
+    drop(raw_mode);
+
+    result
+}
+
+/// Convert the tree to the ncdu JSON export format, fmap only records apparent sizes
+/// so they are used for the disk usage as well
+pub fn tree_to_ncdu(tree: &DirectoryObject) -> JsonValue {
+    let timestamp = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_secs())
+        .unwrap_or(0);
+
+    let root_name = fs::canonicalize(&tree.path)
+        .map(|path| path.to_string_lossy().to_string())
+        .unwrap_or(tree.path.clone());
+
+    JsonValue::Array(vec![
+        NCDU_MAJOR_VERSION.into(),
+        NCDU_MINOR_VERSION.into(),
+        JsonValue::object()
+            .with("progname", env!("CARGO_PKG_NAME").into())
+            .with("progver", env!("CARGO_PKG_VERSION").into())
+            .with("timestamp", timestamp.into()),
+        directory_to_ncdu(tree, &root_name),
+    ])
+}
+
+fn directory_to_ncdu(directory: &DirectoryObject, name: &str) -> JsonValue {
+    let mut entries = vec![JsonValue::object().with("name", name.into())];
+
+    for file in &directory.files {
+        entries.push(
+            JsonValue::object()
+                .with("name", file.full_name.as_str().into())
+                .with("asize", file.size.into())
+                .with("dsize", file.size.into()),
+        );
+    }
+
+    for sub_directory in &directory.directories {
+        entries.push(directory_to_ncdu(sub_directory, &sub_directory.base_name()));
+    }
+
+    JsonValue::Array(entries)
+}
+
+pub fn tree_from_ncdu(export: &JsonValue) -> Result<DirectoryObject, String> {
+    let parts = export
+        .as_array()
+        .ok_or("ncdu export must be a JSON array")?;
+
+    let major_version = parts
+        .first()
+        .and_then(|version| version.as_u64())
+        .ok_or("ncdu export is missing a version")?;
+
+    if major_version != NCDU_MAJOR_VERSION {
+        return Err(format!("unsupported ncdu export version {}", major_version));
+    }
+
+    let root = parts.get(3).ok_or("ncdu export is missing a directory")?;
+    directory_from_ncdu(root, None)
+}
+
+/// Recursion follows the nesting of the JSON, which the parser already keeps within a limit
+fn directory_from_ncdu(
+    value: &JsonValue,
+    parent_path: Option<&str>,
+) -> Result<DirectoryObject, String> {
+    let entries = value
+        .as_array()
+        .ok_or("ncdu directory must be a JSON array")?;
+
+    let name = entries
+        .first()
+        .and_then(|info| info.get("name"))
+        .and_then(|name| name.as_str())
+        .ok_or("ncdu directory is missing a name")?
+        .to_string();
+
+    let path = match parent_path {
+        Some(_) if !is_valid_entry_name(&name) => {
+            return Err(format!("invalid directory name \"{}\"", name))
+        }
+        Some(parent_path) => format!("{}/{}", parent_path, name),
+        None => name.clone(),
+    };
+
+    let mut directory = DirectoryObject::new(&name, &path);
+
+    for entry in entries.iter().skip(1) {
+        match entry {
+            JsonValue::Array(_) => {
+                directory.add_directory(directory_from_ncdu(entry, Some(&path))?);
+            }
+            JsonValue::Object(_) => {
+                // Entries ncdu skipped while scanning have no size information
+                if entry.get("excluded").is_some() {
+                    continue;
+                }
+
+                let file_name = entry
+                    .get("name")
+                    .and_then(|name| name.as_str())
+                    .ok_or("ncdu file is missing a name")?;
+                let size = entry
+                    .get("asize")
+                    .and_then(|size| size.as_u64())
+                    .unwrap_or(0);
+
+                let mut file = file_from_name(file_name, size)?;
+                file.path = format!("{}/{}", path, file_name);
+                directory.add_file(file);
+            }
+            _ => return Err(format!("invalid entry in ncdu directory {}", name)),
+        }
+    }
+
+    directory.update_totals();
+
+    Ok(directory)
+}
+
+pub fn export_ncdu(tree: &DirectoryObject, output_path: &str) -> Result<(), String> {
+    let export = tree_to_ncdu(tree).to_compact_string();
+    if output_path == "-" {
+        println!("{}", export);
+        return Ok(());
+    }
+
+    fs::write(output_path, export)
+        .map_err(|e| format!("failed to write ncdu export to {}: {}", output_path, e))
+}
+
+pub fn import_ncdu(input_path: &str) -> Result<DirectoryObject, String> {
+    let content = fs::read_to_string(input_path)
+        .map_err(|e| format!("failed to read ncdu export {}: {}", input_path, e))?;
+    let export = JsonValue::parse(&content)
+        .map_err(|e| format!("failed to parse ncdu export {}: {}", input_path, e))?;
+    tree_from_ncdu(&export)
+}
@@ -1,4 +1,5 @@
 use std::env;
+use std::path::Path;
 
 #[derive(Debug, PartialEq)]
 pub enum Object {
@@ -47,3 +48,162 @@ pub fn exit_with_error(error: &str, show_help: bool) {
     }
     std::process::exit(0);
 }
+
+/// Format a number with commas between groups of thousands, like 49,980
+pub fn format_count(count: u64) -> String {
+    let digits = count.to_string();
+    let mut formatted = String::new();
+
+    for (i, digit) in digits.chars().enumerate() {
+        if i > 0 && (digits.len() - i).is_multiple_of(3) {
+            formatted.push(',');
+        }
+        formatted.push(digit);
+    }
+
+    formatted
+}
+
+/// Path of an entry relative to the root of a scan, always using / as the separator
+pub fn relative_path(root: &str, path: &str) -> String {
+    match Path::new(path).strip_prefix(root) {
+        Ok(relative) => relative
+            .components()
+            .map(|component| component.as_os_str().to_string_lossy().to_string())
+            .collect::<Vec<String>>()
+            .join("/"),
+        Err(_) => path.to_string(),
+    }
+}
+
+/// Split a comma separated list of file extensions, leading dots are removed
+pub fn parse_extension_list(list: &str) -> Vec<String> {
+    list.split(",")
+        .map(|extension| extension.trim().trim_start_matches('.').to_string())
+        .filter(|extension| !extension.is_empty())
+        .collect()
+}
+
+/// Number of terminal columns a string takes up, wide characters like CJK and emoji take
+/// two columns and combining marks take none
+pub fn display_width(text: &str) -> usize {
+    text.chars().map(char_width).sum()
+}
+
+fn char_width(c: char) -> usize {
+    match c as u32 {
+        0..=0x1f | 0x7f..=0x9f => 0,
+        0x0300..=0x036f
+        | 0x1ab0..=0x1aff
+        | 0x1dc0..=0x1dff
+        | 0x200b..=0x200f
+        | 0x20d0..=0x20ff
+        | 0xfe00..=0xfe0f
+        | 0xfe20..=0xfe2f => 0,
+        0x1100..=0x115f
+        | 0x2e80..=0x303e
+        | 0x3041..=0x33ff
+        | 0x3400..=0x4dbf
+        | 0x4e00..=0x9fff
+        | 0xa000..=0xa4cf
+        | 0xac00..=0xd7a3
+        | 0xf900..=0xfaff
+        | 0xfe30..=0xfe4f
+        | 0xff00..=0xff60
+        | 0xffe0..=0xffe6
+        | 0x1f300..=0x1f64f
+        | 0x1f680..=0x1f6ff
+        | 0x1f900..=0x1f9ff
+        | 0x20000..=0x3fffd => 2,
+        _ => 1,
+    }
+}
+
+/// Shorten text to a display width by replacing its middle with an ellipsis, so both the
+/// start and the extension of a long name stay visible
+pub fn truncate_middle(text: &str, max_width: usize, ellipsis: &str) -> String {
+    if display_width(text) <= max_width {
+        return text.to_string();
+    }
+
+    let ellipsis_width = display_width(ellipsis);
+    if max_width <= ellipsis_width {
+        return ellipsis.chars().take(max_width).collect();
+    }
+
+    let kept_width = max_width - ellipsis_width;
+    let start_width = kept_width.div_ceil(2);
+    let end_width = kept_width - start_width;
+
+    let mut start = String::new();
+    let mut width = 0;
+    for c in text.chars() {
+        if width + char_width(c) > start_width {
+            break;
+        }
+        width += char_width(c);
+        start.push(c);
+    }
+
+    let mut end = Vec::new();
+    width = 0;
+    for c in text.chars().rev() {
+        if width + char_width(c) > end_width {
+            break;
+        }
+        width += char_width(c);
+        end.push(c);
+    }
+
+    format!(
+        "{}{}{}",
+        start,
+        ellipsis,
+        end.into_iter().rev().collect::<String>()
+    )
+}
+
+/// Pad text with spaces to a display width, on the left if it is right aligned
+pub fn pad(text: &str, width: usize, right_align: bool) -> String {
+    let padding = " ".repeat(width.saturating_sub(display_width(text)));
+    if right_align {
+        format!("{}{}", padding, text)
+    } else {
+        format!("{}{}", text, padding)
+    }
+}
+
+/// Quote a path so a shell reads it back unchanged, paths of only safe characters are
+/// left as they are
+pub fn shell_quote(text: &str) -> String {
+    let is_safe = |c: char| c.is_alphanumeric() || "_-./@%+=:,".contains(c);
+    if !text.is_empty() && text.chars().all(is_safe) {
+        return text.to_string();
+    }
+
+    // ANSI-C quoting keeps newlines and other control characters on one line
+    if text.chars().any(|c| c.is_control()) {
+        let mut quoted = String::from("$'");
+        for c in text.chars() {
+            match c {
+                '\n' => quoted.push_str("\\n"),
+                '\t' => quoted.push_str("\\t"),
+                '\r' => quoted.push_str("\\r"),
+                '\\' => quoted.push_str("\\\\"),
+                '\'' => quoted.push_str("\\'"),
+                // \x takes a single byte, so characters above ASCII are written byte by byte
+                c if c.is_control() => {
+                    let mut bytes = [0; 4];
+                    for byte in c.encode_utf8(&mut bytes).bytes() {
+                        quoted.push_str(&format!("\\x{:02x}", byte));
+                    }
+                }
+                c => quoted.push(c),
+            }
+        }
+        quoted.push('\'');
+        return quoted;
+    }
+
+    format!("'{}'", text.replace('\'', "'\\''"))
+}
@@ -0,0 +1,378 @@
+use crate::display::{display, DisplayOptions};
+use crate::scan::{create_tree, file_from_path, read_metadata, DirectoryObject, IgnoreLists};
+use crate::system::notify::{ChangeNotifier, Changes};
+use crate::OS;
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::thread;
+use std::time::{Duration, Instant, SystemTime};
+
+/// How long a changed entry stays highlighted after the change is seen
+const HIGHLIGHT_DURATION: Duration = Duration::from_secs(10);
+
+struct EntryState {
+    modified: Option<SystemTime>,
+    size: u64,
+}
+
+/// Keeps a scanned tree up to date, either by re-checking the directories the operating
+/// system reports as changed or by polling the whole tree
+pub struct Watcher {
+    pub tree: DirectoryObject,
+    ignored: IgnoreLists,
+    states: HashMap<String, EntryState>,
+    recent_changes: HashMap<String, Instant>,
+}
+
+impl Watcher {
+    pub fn new(path: &String, os: &OS) -> Watcher {
+        let ignored = IgnoreLists::from_config(os);
+        let tree = create_tree(path, &ignored);
+        let mut states = HashMap::new();
+        record_states(&tree, &mut states);
+
+        Watcher {
+            tree,
+            ignored,
+            states,
+            recent_changes: HashMap::new(),
+        }
+    }
+
+    /// Check the tree for changes, returns true if anything changed since the last poll
+    pub fn poll(&mut self) -> bool {
+        let mut changed_paths = Vec::new();
+        poll_directory(
+            &mut self.tree,
+            &self.ignored,
+            &mut self.states,
+            &mut changed_paths,
+        );
+        self.record_changes(changed_paths)
+    }
+
+    /// Check only the given directories for changes, returns true if anything changed
+    pub fn refresh(&mut self, directories: &HashSet<String>) -> bool {
+        let mut changed_paths = Vec::new();
+        refresh_directories(
+            &mut self.tree,
+            directories,
+            &self.ignored,
+            &mut self.states,
+            &mut changed_paths,
+        );
+        self.record_changes(changed_paths)
+    }
+
+    /// Paths of every directory in the tree
+    pub fn directory_paths(&self) -> Vec<String> {
+        let mut paths = Vec::new();
+        collect_directory_paths(&self.tree, &mut paths);
+        paths
+    }
+
+    /// Highlight the changed paths and drop expired highlights, returns true if either happened
+    fn record_changes(&mut self, changed_paths: Vec<String>) -> bool {
+        let now = Instant::now();
+        for path in &changed_paths {
+            self.recent_changes.insert(path.to_string(), now);
+        }
+
+        let expired_count = self.recent_changes.len();
+        self.recent_changes
+            .retain(|_, changed_at| now.duration_since(*changed_at) < HIGHLIGHT_DURATION);
+
+        !changed_paths.is_empty() || expired_count != self.recent_changes.len()
+    }
+
+    pub fn recently_changed_paths(&self) -> impl Iterator<Item = &String> {
+        self.recent_changes.keys()
+    }
+}
+
+fn read_state(path: &str) -> Option<EntryState> {
+    fs::metadata(path).ok().map(|metadata| EntryState {
+        modified: metadata.modified().ok(),
+        size: metadata.len(),
+    })
+}
+
+fn record_states(directory: &DirectoryObject, states: &mut HashMap<String, EntryState>) {
+    if let Some(state) = read_state(&directory.path) {
+        states.insert(directory.path.clone(), state);
+    }
+
+    for file in &directory.files {
+        if let Some(state) = read_state(&file.path) {
+            states.insert(file.path.clone(), state);
+        }
+    }
+
+    for sub_directory in &directory.directories {
+        record_states(sub_directory, states);
+    }
+}
+
+fn forget_states(directory: &DirectoryObject, states: &mut HashMap<String, EntryState>) {
+    states.remove(&directory.path);
+    for file in &directory.files {
+        states.remove(&file.path);
+    }
+    for sub_directory in &directory.directories {
+        forget_states(sub_directory, states);
+    }
+}
+
+fn collect_directory_paths(directory: &DirectoryObject, paths: &mut Vec<String>) {
+    paths.push(directory.path.clone());
+    for sub_directory in &directory.directories {
+        collect_directory_paths(sub_directory, paths);
+    }
+}
+
+fn poll_directory(
+    directory: &mut DirectoryObject,
+    ignored: &IgnoreLists,
+    states: &mut HashMap<String, EntryState>,
+    changed_paths: &mut Vec<String>,
+) {
+    check_directory(directory, false, ignored, states, changed_paths);
+
+    for sub_directory in directory.directories.iter_mut() {
+        poll_directory(sub_directory, ignored, states, changed_paths);
+    }
+}
+
+fn refresh_directories(
+    directory: &mut DirectoryObject,
+    directories: &HashSet<String>,
+    ignored: &IgnoreLists,
+    states: &mut HashMap<String, EntryState>,
+    changed_paths: &mut Vec<String>,
+) {
+    // The operating system only reports a directory when its entries changed, but the
+    // modification time of the directory can stay the same within its resolution
+    if directories.contains(&directory.path) {
+        check_directory(directory, true, ignored, states, changed_paths);
+    }
+
+    for sub_directory in directory.directories.iter_mut() {
+        refresh_directories(sub_directory, directories, ignored, states, changed_paths);
+    }
+}
+
+/// Check the entries directly in a directory, the listing is read again when forced or when
+/// the modification time of the directory changed
+fn check_directory(
+    directory: &mut DirectoryObject,
+    force_listing: bool,
+    ignored: &IgnoreLists,
+    states: &mut HashMap<String, EntryState>,
+    changed_paths: &mut Vec<String>,
+) {
+    let directory_state = read_state(&directory.path);
+    let listing_changed = force_listing
+        || match (&directory_state, states.get(&directory.path)) {
+            (Some(new), Some(old)) => new.modified != old.modified,
+            _ => true,
+        };
+
+    // Adding, removing or renaming an entry updates the modification time of its parent,
+    // so the directory listing only has to be read again when that changes
+    if listing_changed {
+        if let Some(state) = directory_state {
+            states.insert(directory.path.clone(), state);
+        }
+        refresh_listing(directory, ignored, states, changed_paths);
+    }
+
+    for file in directory.files.iter_mut() {
+        let new_state = match read_state(&file.path) {
+            Some(state) => state,
+            None => continue,
+        };
+
+        let is_changed = match states.get(&file.path) {
+            Some(old_state) => {
+                old_state.size != new_state.size || old_state.modified != new_state.modified
+            }
+            None => true,
+        };
+
+        if is_changed {
+            *file = file_from_path(std::path::Path::new(&file.path), new_state.size);
+            file.metadata = read_metadata(&file.path);
+            changed_paths.push(file.path.clone());
+            states.insert(file.path.clone(), new_state);
+        }
+    }
+
+    directory.update_totals();
+}
+
+fn refresh_listing(
+    directory: &mut DirectoryObject,
+    ignored: &IgnoreLists,
+    states: &mut HashMap<String, EntryState>,
+    changed_paths: &mut Vec<String>,
+) {
+    let mut file_paths = Vec::new();
+    let mut directory_paths = Vec::new();
+
+    if let Ok(contents) = fs::read_dir(&directory.path) {
+        for entry in contents.flatten() {
+            let entry_path = entry.path();
+            let entry_name = entry.file_name().to_string_lossy().to_string();
+
+            if entry_path.is_dir() {
+                if !ignored.ignores_directory(&entry_name) {
+                    directory_paths.push(entry_path.to_string_lossy().to_string());
+                }
+            } else if !ignored.ignores_file(&entry_name) {
+                file_paths.push(entry_path);
+            }
+        }
+    }
+
+    // Directories can hold tens of thousands of entries, so entries are looked up in sets
+    let listed_files: HashSet<String> = file_paths
+        .iter()
+        .map(|path| path.to_string_lossy().to_string())
+        .collect();
+    let listed_directories: HashSet<&String> = directory_paths.iter().collect();
+
+    let file_count_before = directory.files.len();
+    directory.files.retain(|file| {
+        let exists = listed_files.contains(&file.path);
+        if !exists {
+            states.remove(&file.path);
+        }
+        exists
+    });
+    let mut has_removed_entries = directory.files.len() != file_count_before;
+
+    let known_files: HashSet<String> = directory
+        .files
+        .iter()
+        .map(|file| file.path.clone())
+        .collect();
+    for file_path in file_paths {
+        let path_string = file_path.to_string_lossy().to_string();
+        if known_files.contains(&path_string) {
+            continue;
+        }
+
+        let state = match read_state(&path_string) {
+            Some(state) => state,
+            None => continue,
+        };
+
+        let mut file = file_from_path(&file_path, state.size);
+        file.metadata = read_metadata(&path_string);
+        directory.add_file(file);
+        states.insert(path_string.clone(), state);
+        changed_paths.push(path_string);
+    }
+
+    let directory_count_before = directory.directories.len();
+    directory.directories.retain(|sub_directory| {
+        let exists = listed_directories.contains(&sub_directory.path);
+        if !exists {
+            forget_states(sub_directory, states);
+        }
+        exists
+    });
+    has_removed_entries |= directory.directories.len() != directory_count_before;
+
+    let known_directories: HashSet<String> = directory
+        .directories
+        .iter()
+        .map(|sub_directory| sub_directory.path.clone())
+        .collect();
+    for directory_path in directory_paths {
+        if known_directories.contains(&directory_path) {
+            continue;
+        }
+
+        let sub_directory = create_tree(&directory_path, ignored);
+        record_states(&sub_directory, states);
+        changed_paths.push(directory_path);
+        directory.add_directory(sub_directory);
+    }
+
+    // Removed entries can not be highlighted, so highlight the directory they were in instead
+    if has_removed_entries {
+        changed_paths.push(directory.path.clone());
+    }
+}
+
+/// Watch every directory in the tree, returns None once the notifier can not do so anymore
+fn sync_notifier(notifier: Option<ChangeNotifier>, watcher: &Watcher) -> Option<ChangeNotifier> {
+    let mut notifier = notifier?;
+    notifier.sync_directories(&watcher.directory_paths()).ok()?;
+    Some(notifier)
+}
+
+pub fn watch(path: &String, options: &mut DisplayOptions, interval: Duration, os: &OS) {
+    // The notifier is started before the scan so changes made during the scan are not missed
+    let notifier = ChangeNotifier::new(path).ok();
+    let mut watcher = Watcher::new(path, os);
+    let mut notifier = sync_notifier(notifier, &watcher);
+
+    let mut needs_redraw = true;
+    let mut last_redraw: Option<Instant> = None;
+
+    loop {
+        // Files being written report changes continuously, so changes are applied as they
+        // arrive but the tree is redrawn at most once per interval
+        let is_redraw_due = last_redraw.is_none_or(|redrawn_at| redrawn_at.elapsed() >= interval);
+        if needs_redraw && is_redraw_due {
+            options.highlighted_paths = watcher.recently_changed_paths().cloned().collect();
+
+            // Clear the screen and move the cursor to the top left before redrawing
+            print!("\u{001b}[2J\u{001b}[H");
+            if notifier.is_some() {
+                println!("watching {}, press ctrl+c to stop\n", path);
+            } else {
+                println!(
+                    "watching {} every {:.1}s, press ctrl+c to stop\n",
+                    path,
+                    interval.as_secs_f64()
+                );
+            }
+            display(&watcher.tree, options, os);
+
+            needs_redraw = false;
+            last_redraw = Some(Instant::now());
+        }
+
+        let was_changed = match &mut notifier {
+            Some(active_notifier) => {
+                let timeout = match last_redraw {
+                    Some(redrawn_at) if needs_redraw => {
+                        interval.saturating_sub(redrawn_at.elapsed())
+                    }
+                    _ => interval,
+                };
+
+                let was_changed = match active_notifier.wait(timeout) {
+                    Ok(Changes::Directories(directories)) => watcher.refresh(&directories),
+                    Ok(Changes::Unknown) => watcher.poll(),
+                    Err(_) => {
+                        // Polling takes over when the notifications stop working
+                        notifier = None;
+                        watcher.poll()
+                    }
+                };
+
+                notifier = sync_notifier(notifier, &watcher);
+                was_changed
+            }
+            None => {
+                thread::sleep(interval);
+                watcher.poll()
+            }
+        };
+        needs_redraw |= was_changed;
+    }
+}